@@ -0,0 +1,113 @@
+//! Options for relaxing or restricting the accepted JSON
+
+/// Deserializer configuration
+///
+/// The default accepts strict JSON. Options are enabled with the builder methods:
+///
+/// ```
+/// # use serde_json_core::de::Config;
+/// let config = Config::default()
+///     .allow_comments(true)
+///     .allow_trailing_commas(true);
+///
+/// let (value, _): ([u8; 2], _) =
+///     serde_json_core::from_str_with_config("[1, 2, /* three */]", &config).unwrap();
+/// assert_eq!(value, [1, 2]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Config {
+    pub(crate) allow_comments: bool,
+    pub(crate) allow_trailing_commas: bool,
+    pub(crate) tuple_from_object: bool,
+    pub(crate) lenient_whitespace: bool,
+    pub(crate) char_from_code_point: bool,
+    pub(crate) enum_from_index: bool,
+    pub(crate) max_string_len: Option<usize>,
+    pub(crate) max_elements: Option<usize>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) quoted_non_finite: bool,
+    pub(crate) tuple_prefix: bool,
+}
+
+impl Config {
+    /// Treat `// line` and `/* block */` comments as whitespace
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Accept a comma after the last element of an array or the last member of an object
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.allow_trailing_commas = allow;
+        self
+    }
+
+    /// Accept arrays longer than a tuple, tuple struct or fixed-size array, skipping the elements
+    /// after the ones it holds, like `(1, 2)` from `[1,2,3,4]`
+    pub fn tuple_prefix(mut self, allow: bool) -> Self {
+        self.tuple_prefix = allow;
+        self
+    }
+
+    /// Also accept tuples and tuple structs as objects whose keys are the positions of the
+    /// elements in order, like `{"0":1,"1":2}` for `(1, 2)`
+    pub fn tuple_from_object(mut self, allow: bool) -> Self {
+        self.tuple_from_object = allow;
+        self
+    }
+
+    /// Also skip form feed (`\x0C`) and vertical tab (`\x0B`) as whitespace between tokens
+    pub fn lenient_whitespace(mut self, allow: bool) -> Self {
+        self.lenient_whitespace = allow;
+        self
+    }
+
+    /// Also accept a `char` as its Unicode code point, like `65` for `'A'`
+    pub fn char_from_code_point(mut self, allow: bool) -> Self {
+        self.char_from_code_point = allow;
+        self
+    }
+
+    /// Also accept floats that aren't finite as the strings `"NaN"`, `"Infinity"` and
+    /// `"-Infinity"`, as written by [`NonFiniteFloat::Quoted`](crate::ser::NonFiniteFloat::Quoted)
+    pub fn quoted_non_finite(mut self, allow: bool) -> Self {
+        self.quoted_non_finite = allow;
+        self
+    }
+
+    /// Also accept unit variants of enums as their index, like `1` for the second variant
+    pub fn enum_from_index(mut self, allow: bool) -> Self {
+        self.enum_from_index = allow;
+        self
+    }
+
+    /// Fail with [`Error::StringTooLong`](crate::de::Error::StringTooLong) on strings, including
+    /// object keys, longer than `len` bytes before unescaping; unlimited by default
+    ///
+    /// This stops scanning a giant string early instead of relying on the input being bounded.
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = Some(len);
+        self
+    }
+
+    /// Fail with [`Error::TooManyElements`](crate::de::Error::TooManyElements) on arrays with more
+    /// than `len` elements and objects with more than `len` members; unlimited by default
+    ///
+    /// Unknown struct fields and other skipped values count too, so this bounds the parsing time
+    /// of every array and object.
+    pub fn max_elements(mut self, len: usize) -> Self {
+        self.max_elements = Some(len);
+        self
+    }
+
+    /// Fail with [`Error::RecursionLimitExceeded`](crate::de::Error::RecursionLimitExceeded)
+    /// instead of parsing arrays and objects nested deeper than `depth`; unlimited by default
+    ///
+    /// Every level of nesting recurses once, so this bounds the stack usage of parsing untrusted
+    /// input, also of values that are skipped.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+}
@@ -4,12 +4,44 @@ use crate::de::{Deserializer, Error};
 
 pub struct MapAccess<'a, 'b, 's> {
     de: &'a mut Deserializer<'b, 's>,
-    first: bool,
+    /// Number of members so far
+    len: usize,
+    /// Keys of the members to deserialize, the others are skipped
+    fields: Option<&'s [&'s str]>,
+    /// The escaped key of the current member, while collecting extras
+    key: Option<&'b str>,
 }
 
 impl<'a, 'b, 's> MapAccess<'a, 'b, 's> {
     pub(crate) fn new(de: &'a mut Deserializer<'b, 's>) -> Self {
-        MapAccess { de, first: true }
+        // Only the outermost object is masked
+        let fields = de.outer_field_mask.take();
+        MapAccess {
+            de,
+            len: 0,
+            fields,
+            key: None,
+        }
+    }
+
+    /// Skips the member at the start of a key if its key isn't selected
+    ///
+    /// Returns whether the member was skipped.
+    fn skip_unselected(&mut self) -> Result<bool, Error> {
+        let fields = match self.fields {
+            Some(fields) => fields,
+            None => return Ok(false),
+        };
+
+        let start = self.de.index;
+        let key = self.de.parse_str()?;
+        if fields.contains(&key) {
+            self.de.index = start;
+            return Ok(false);
+        }
+
+        self.de.parse_object_colon()?;
+        de::Deserialize::deserialize(&mut *self.de).map(|de::IgnoredAny| true)
     }
 }
 
@@ -20,30 +52,43 @@ impl<'a, 'de, 's> de::MapAccess<'de> for MapAccess<'a, 'de, 's> {
     where
         K: de::DeserializeSeed<'de>,
     {
-        let peek = match self
-            .de
-            .parse_whitespace()
-            .ok_or(Error::EofWhileParsingObject)?
-        {
-            b'}' => return Ok(None),
-            b',' if !self.first => {
-                self.de.eat_char();
-                self.de.parse_whitespace()
-            }
-            b => {
-                if self.first {
-                    self.first = false;
-                    Some(b)
-                } else {
-                    return Err(Error::ExpectedObjectCommaOrEnd);
+        loop {
+            let peek = match self
+                .de
+                .parse_whitespace()
+                .ok_or(Error::EofWhileParsingObject)?
+            {
+                b'}' => return Ok(None),
+                b',' if self.len > 0 => {
+                    self.de.eat_char();
+                    self.de.parse_whitespace()
                 }
-            }
-        };
-
-        match peek.ok_or(Error::EofWhileParsingValue)? {
-            b'"' => seed.deserialize(MapKey { de: &mut *self.de }).map(Some),
-            b'}' => Err(Error::TrailingComma),
-            _ => Err(Error::KeyMustBeAString),
+                b => {
+                    if self.len == 0 {
+                        Some(b)
+                    } else {
+                        return Err(Error::ExpectedObjectCommaOrEnd);
+                    }
+                }
+            };
+
+            return match peek.ok_or(Error::EofWhileParsingValue)? {
+                b'"' => {
+                    self.len = self.de.check_elements(self.len)?;
+                    if self.skip_unselected()? {
+                        continue;
+                    }
+                    if self.de.extras.is_some() {
+                        let start = self.de.index;
+                        self.key = Some(self.de.parse_str()?);
+                        self.de.index = start;
+                    }
+                    seed.deserialize(MapKey { de: &mut *self.de }).map(Some)
+                }
+                b'}' if self.de.config.allow_trailing_commas => Ok(None),
+                b'}' => Err(Error::TrailingComma),
+                _ => Err(Error::KeyMustBeAString),
+            };
         }
     }
 
@@ -53,6 +98,10 @@ impl<'a, 'de, 's> de::MapAccess<'de> for MapAccess<'a, 'de, 's> {
     {
         self.de.parse_object_colon()?;
 
+        if let Some(key) = self.key.take() {
+            self.de.parse_whitespace();
+            self.de.member = Some((key, self.de.index));
+        }
         seed.deserialize(&mut *self.de)
     }
 }
@@ -61,77 +110,95 @@ struct MapKey<'a, 'b, 's> {
     de: &'a mut Deserializer<'b, 's>,
 }
 
+/// Deserializes a key which isn't a string, like `"5"` for an integer, from inside the quotes
+macro_rules! deserialize_quoted {
+    ($self:ident, $visitor:ident, $deserialize_fn:ident, $error:expr) => {{
+        let key = $self.de.parse_str()?;
+        // The value must fill the quotes, without whitespace around it
+        if key.starts_with(|c: char| c.is_ascii_whitespace()) {
+            return Err($error);
+        }
+        let mut de = Deserializer::new(key.as_bytes(), None);
+        let value = de::Deserializer::$deserialize_fn(&mut de, $visitor)?;
+        if de.index != key.len() {
+            return Err($error);
+        }
+        Ok(value)
+    }};
+}
+
 impl<'de, 'a, 's> de::Deserializer<'de> for MapKey<'a, 'de, 's> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Error>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        // Object keys are always strings
+        self.de.parse_and_visit_str(visitor)
     }
 
-    fn deserialize_bool<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_bool, Error::InvalidType)
     }
 
-    fn deserialize_i8<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_i8, Error::InvalidNumber)
     }
 
-    fn deserialize_i16<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_i16, Error::InvalidNumber)
     }
 
-    fn deserialize_i32<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_i32, Error::InvalidNumber)
     }
 
-    fn deserialize_i64<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_i64, Error::InvalidNumber)
     }
 
-    fn deserialize_u8<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_u8, Error::InvalidNumber)
     }
 
-    fn deserialize_u16<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_u16, Error::InvalidNumber)
     }
 
-    fn deserialize_u32<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_u32, Error::InvalidNumber)
     }
 
-    fn deserialize_u64<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        unreachable!()
+        deserialize_quoted!(self, visitor, deserialize_u64, Error::InvalidNumber)
     }
 
     fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
@@ -159,7 +226,7 @@ impl<'de, 'a, 's> de::Deserializer<'de> for MapKey<'a, 'de, 's> {
     where
         V: Visitor<'de>,
     {
-        self.de.deserialize_str(visitor)
+        self.de.parse_and_visit_str(visitor)
     }
 
     fn deserialize_string<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
@@ -1,15 +1,25 @@
 //! Deserialize JSON data to a Rust data structure
 
+// Larger custom error messages are an explicit opt-in, so allow the error to be big.
+#![cfg_attr(
+    feature = "custom-error-messages-128",
+    allow(clippy::large_enum_variant, clippy::result_large_err)
+)]
+
+use core::ops::Range;
 use core::str::FromStr;
 use core::{fmt, str};
 
 use serde::de::{self, Visitor};
 use serde::Serialize;
 
+pub use self::config::Config;
 use self::enum_::{UnitVariantAccess, VariantAccess};
 use self::map::MapAccess;
-use self::seq::SeqAccess;
+use self::seq::{IndexedObjectAccess, SeqAccess};
+use crate::str::RawValue;
 
+mod config;
 mod enum_;
 mod map;
 mod seq;
@@ -17,6 +27,20 @@ mod seq;
 /// Deserialization result
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Maximum length of the message kept in [`Error::CustomErrorWithMessage`]; longer messages are
+/// truncated.
+///
+/// This is 64 by default and can be raised with the `custom-error-messages-128` or
+/// `custom-error-messages-256` features.
+#[cfg(feature = "custom-error-messages")]
+pub const CUSTOM_ERROR_MESSAGE_LEN: usize = if cfg!(feature = "custom-error-messages-256") {
+    256
+} else if cfg!(feature = "custom-error-messages-128") {
+    128
+} else {
+    64
+};
+
 /// This type represents all possible errors that can occur when deserializing JSON data
 #[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 #[cfg_attr(not(feature = "custom-error-messages"), derive(Copy))]
@@ -24,6 +48,8 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[non_exhaustive]
 pub enum Error {
     /// Can’t parse a value without knowing its expected type.
+    ///
+    /// No longer returned: `deserialize_any` is supported.
     AnyIsUnsupported,
 
     /// Cannot parse a sequence of bytes.
@@ -59,6 +85,13 @@ pub enum Error {
     /// Expected this character to start a JSON value.
     ExpectedSomeValue,
 
+    /// Invalid number of elements for the type, e.g. more elements than a `heapless::Vec` can
+    /// hold or fewer than a tuple requires.
+    ///
+    /// With the `custom-error-messages` feature a `CustomErrorWithMessage` describing the expected
+    /// length is returned instead.
+    InvalidLength,
+
     /// Invalid number.
     InvalidNumber,
 
@@ -74,6 +107,21 @@ pub enum Error {
     /// Escaped String length exceeds buffer size
     EscapedStringIsTooLong,
 
+    /// Array of bytes exceeds buffer size
+    ByteArrayIsTooLong,
+
+    /// String is longer than allowed by [`Config::max_string_len`].
+    StringTooLong,
+
+    /// Array or object has more elements than allowed by [`Config::max_elements`].
+    TooManyElements,
+
+    /// Arrays and objects are nested deeper than [`Config::max_depth`] allows.
+    RecursionLimitExceeded,
+
+    /// Input split into several chunks doesn't fit into the scratch buffer.
+    ChunksTooLong,
+
     /// Object key is not a string.
     KeyMustBeAString,
 
@@ -89,7 +137,8 @@ pub enum Error {
     /// Error with a custom message that was preserved.
     #[cfg(feature = "custom-error-messages")]
     CustomErrorWithMessage(
-        #[cfg_attr(feature = "defmt", defmt(Debug2Format))] heapless::String<64>,
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+        heapless::String<CUSTOM_ERROR_MESSAGE_LEN>,
     ),
 }
 
@@ -98,16 +147,64 @@ impl serde::de::StdError for Error {}
 impl From<crate::str::StringUnescapeError> for Error {
     fn from(error: crate::str::StringUnescapeError) -> Self {
         match error {
-            crate::str::StringUnescapeError::InvalidEscapeSequence => Self::InvalidEscapeSequence,
+            crate::str::StringUnescapeError::UnknownEscape(_)
+            | crate::str::StringUnescapeError::UnterminatedEscape
+            | crate::str::StringUnescapeError::IncompleteUnicode
+            | crate::str::StringUnescapeError::InvalidUnicodeDigits
+            | crate::str::StringUnescapeError::LoneSurrogate => Self::InvalidEscapeSequence,
+            crate::str::StringUnescapeError::WriteFailed => Self::EscapedStringIsTooLong,
         }
     }
 }
 
+/// Unescapes `escaped_string` into the start of `buf`
+/// Returns the length of the unescaped string
+fn unescape(escaped_string: &str, buf: &mut [u8]) -> Result<usize> {
+    let mut write_position = 0;
+
+    for fragment in crate::str::EscapedStr(escaped_string).fragments() {
+        let char_encode_buffer = &mut [0; 4];
+
+        let unescaped_bytes = match fragment? {
+            crate::str::EscapedStringFragment::NotEscaped(fragment) => fragment.as_bytes(),
+            crate::str::EscapedStringFragment::Escaped(c) => {
+                c.encode_utf8(char_encode_buffer).as_bytes()
+            }
+        };
+
+        buf[write_position..]
+            .get_mut(..unescaped_bytes.len())
+            .ok_or(Error::EscapedStringIsTooLong)?
+            .copy_from_slice(unescaped_bytes);
+
+        write_position += unescaped_bytes.len();
+    }
+
+    Ok(write_position)
+}
+
 /// A structure that deserializes Rust values from JSON in a buffer.
 pub struct Deserializer<'b, 's> {
     slice: &'b [u8],
     index: usize,
     string_unescape_buffer: Option<&'s mut [u8]>,
+    config: Config,
+    /// Number of arrays and objects around the next byte to parse
+    depth: usize,
+    stats: Option<Stats>,
+    /// Keys of the outermost object's members to deserialize, see
+    /// [`Deserializer::with_field_mask`]
+    field_mask: Option<&'s [&'s str]>,
+    /// `field_mask` until the first array or object of the current document
+    outer_field_mask: Option<&'s [&'s str]>,
+    /// What's left of the buffer that escaped strings are unescaped into and then borrowed from,
+    /// see [`from_slice_auto`]
+    scratch: Option<&'b mut [u8]>,
+    /// Called with the members that structs skip, see [`Deserializer::with_extras`]
+    extras: Option<&'s mut dyn FnMut(&'b str, RawValue<'b>)>,
+    /// The escaped key of the member whose value starts at the given index, while collecting
+    /// extras
+    member: Option<(&'b str, usize)>,
 }
 
 impl<'a, 's> Deserializer<'a, 's> {
@@ -121,13 +218,239 @@ impl<'a, 's> Deserializer<'a, 's> {
             slice,
             index: 0,
             string_unescape_buffer,
+            config: Config::default(),
+            depth: 0,
+            stats: None,
+            field_mask: None,
+            outer_field_mask: None,
+            scratch: None,
+            extras: None,
+            member: None,
+        }
+    }
+
+    /// Use `config` instead of the default strict configuration
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Call `extras` with the key and text of every object member that a struct skips instead of
+    /// deserializing it, at any depth
+    ///
+    /// These are the members that `Deserialize` implementations pass to `deserialize_ignored_any`,
+    /// like unknown fields of derived structs without `deny_unknown_fields`. Keys are passed as
+    /// written, without unescaping them. See [`from_slice_with_extras`].
+    pub fn with_extras(mut self, extras: &'s mut dyn FnMut(&'a str, RawValue<'a>)) -> Self {
+        self.extras = Some(extras);
+        self
+    }
+
+    /// Skip the members of the outermost object whose keys aren't in `fields`, see
+    /// [`from_slice_fields`]
+    pub fn with_field_mask(mut self, fields: &'s [&'s str]) -> Self {
+        self.field_mask = Some(fields);
+        self.outer_field_mask = Some(fields);
+        self
+    }
+
+    /// Count the values parsed from now on, see [`Deserializer::stats`]
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(Stats::default());
+        self
+    }
+
+    /// Return how many values were parsed so far, if counting was enabled with
+    /// [`Deserializer::with_stats`]
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats
+    }
+
+    /// Return the offset into the input of the next byte to parse
+    ///
+    /// After an error this is where parsing stopped, see [`Error::at`].
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Start over parsing `slice`, keeping the unescape buffer, configuration, field mask and
+    /// extras callback
+    ///
+    /// This allows parsing many small messages with one `Deserializer`. Counted values, if any,
+    /// are cleared.
+    pub fn reset(&mut self, slice: &'a [u8]) {
+        self.slice = slice;
+        self.index = 0;
+        self.depth = 0;
+        if let Some(stats) = &mut self.stats {
+            *stats = Stats::default();
         }
+        self.outer_field_mask = self.field_mask;
+        // Strings of the previous document still borrow the used part
+        self.scratch = None;
+        self.member = None;
     }
 
     fn eat_char(&mut self) {
         self.index += 1;
     }
 
+    fn count(&mut self, element: Element) {
+        if let Some(stats) = &mut self.stats {
+            match element {
+                Element::Object => stats.objects += 1,
+                Element::Array => stats.arrays += 1,
+                Element::Scalar => stats.scalars += 1,
+            }
+        }
+    }
+
+    /// Parses a string, unescaping it if there is a buffer to do so, and passes it to `visitor`
+    fn parse_and_visit_str<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'a>,
+    {
+        let escaped_string = self.parse_str()?;
+
+        // If the escaped string doesn't contain '\\', it' can't have any escaped characters
+        if !escaped_string.as_bytes().contains(&b'\\') {
+            return visitor.visit_borrowed_str(escaped_string);
+        }
+
+        // Unescaped strings are kept in the scratch buffer for as long as the input is borrowed
+        if let Some(scratch) = self.scratch.take() {
+            let len = unescape(escaped_string, scratch)?;
+            let (unescaped, rest) = scratch.split_at_mut(len);
+            self.scratch = Some(rest);
+            let unescaped: &'a [u8] = unescaped;
+            return visitor.visit_borrowed_str(
+                str::from_utf8(unescaped).map_err(|_| Error::InvalidUnicodeCodePoint)?,
+            );
+        }
+
+        // If the unescape buffer is not provided, skip unescaping strings
+        let Some(string_unescape_buffer) = self.string_unescape_buffer.as_deref_mut() else {
+            return visitor.visit_borrowed_str(escaped_string);
+        };
+
+        let len = unescape(escaped_string, string_unescape_buffer)?;
+
+        visitor.visit_str(
+            str::from_utf8(&string_unescape_buffer[..len])
+                .map_err(|_| Error::InvalidUnicodeCodePoint)?,
+        )
+    }
+
+    /// Skips a string, number, `true`, `false` or `null`
+    ///
+    /// This is separate from `deserialize_ignored_any`, which recurses for nested arrays and
+    /// objects, to keep the stack usage per level of nesting down.
+    #[inline(never)]
+    fn skip_scalar(&mut self) -> Result<()> {
+        match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
+            b'"' => {
+                // Strings are skipped without unescaping them, but escape sequences still have to
+                // be valid.
+                self.count(Element::Scalar);
+                let escaped_string = self.parse_str()?;
+                for fragment in crate::str::EscapedStr(escaped_string).fragments() {
+                    fragment?;
+                }
+                Ok(())
+            }
+            b't' => {
+                self.eat_char();
+                self.count(Element::Scalar);
+                self.parse_ident(b"rue")
+            }
+            b'f' => {
+                self.eat_char();
+                self.count(Element::Scalar);
+                self.parse_ident(b"alse")
+            }
+            b'n' => {
+                self.eat_char();
+                self.count(Element::Scalar);
+                self.parse_ident(b"ull")
+            }
+            b'-' | b'0'..=b'9' => {
+                self.count(Element::Scalar);
+                self.skip_number()
+            }
+            _ => Err(Error::ExpectedSomeValue),
+        }
+    }
+
+    /// Skips the next value and returns its text, which is valid strict JSON
+    fn parse_raw(&mut self) -> Result<&'a str> {
+        // A `RawValue` field isn't skipped, and neither are the members within the value
+        self.member = None;
+        let extras = self.extras.take();
+        self.parse_whitespace();
+        let start = self.index;
+        <de::IgnoredAny as de::Deserialize<'_>>::deserialize(&mut *self)?;
+        self.extras = extras;
+        // Only ASCII is accepted outside of strings, and strings were checked to be UTF-8
+        let raw = str::from_utf8(&self.slice[start..self.index])
+            .map_err(|_| Error::InvalidUnicodeCodePoint)?;
+        // The text is written out verbatim, so it has to be strict JSON even if the rest of the
+        // input may have comments and the like
+        let c = &self.config;
+        if c.allow_comments || c.allow_trailing_commas || c.lenient_whitespace {
+            validate(raw.as_bytes())?;
+        }
+        Ok(raw)
+    }
+
+    /// Passes the member whose value is at the current position to the `extras` callback, if
+    /// there is one, returning whether there was
+    ///
+    /// The member is skipped, rather than a value within it.
+    #[inline(never)]
+    fn collect_extra(&mut self) -> Result<bool> {
+        self.parse_whitespace();
+        match self.member.take() {
+            Some((key, at)) if at == self.index => {
+                let raw = RawValue(self.parse_raw()?);
+                if let Some(extras) = &mut self.extras {
+                    extras(key, raw);
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Enters an array or object, unless that nests them deeper than allowed
+    fn enter(&mut self) -> Result<()> {
+        match self.config.max_depth {
+            Some(max) if self.depth >= max => Err(Error::RecursionLimitExceeded),
+            _ => {
+                self.depth += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Counts another element of an array or object that has `len` so far, unless that's too many
+    fn check_elements(&self, len: usize) -> Result<usize> {
+        match self.config.max_elements {
+            Some(max) if len >= max => Err(Error::TooManyElements),
+            _ => Ok(len + 1),
+        }
+    }
+
+    /// Parses the elements of an array of bytes into `buf`, returning their number
+    fn parse_bytes(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut seq = SeqAccess::new(self);
+        let mut len = 0;
+        while let Some(byte) = de::SeqAccess::next_element(&mut seq)? {
+            *buf.get_mut(len).ok_or(Error::ByteArrayIsTooLong)? = byte;
+            len += 1;
+        }
+        Ok(len)
+    }
+
     /// Check whether there is any unexpected data left in the buffer
     /// and return the amount of data consumed
     pub fn end(&mut self) -> Result<usize> {
@@ -138,6 +461,7 @@ impl<'a, 's> Deserializer<'a, 's> {
     }
 
     fn end_seq(&mut self) -> Result<()> {
+        self.depth -= 1;
         match self.parse_whitespace().ok_or(Error::EofWhileParsingList)? {
             b']' => {
                 self.eat_char();
@@ -146,6 +470,10 @@ impl<'a, 's> Deserializer<'a, 's> {
             b',' => {
                 self.eat_char();
                 match self.parse_whitespace() {
+                    Some(b']') if self.config.allow_trailing_commas => {
+                        self.eat_char();
+                        Ok(())
+                    }
                     Some(b']') => Err(Error::TrailingComma),
                     _ => Err(Error::TrailingCharacters),
                 }
@@ -155,6 +483,7 @@ impl<'a, 's> Deserializer<'a, 's> {
     }
 
     fn end_map(&mut self) -> Result<()> {
+        self.depth -= 1;
         match self
             .parse_whitespace()
             .ok_or(Error::EofWhileParsingObject)?
@@ -163,6 +492,16 @@ impl<'a, 's> Deserializer<'a, 's> {
                 self.eat_char();
                 Ok(())
             }
+            b',' if self.config.allow_trailing_commas => {
+                self.eat_char();
+                match self.parse_whitespace() {
+                    Some(b'}') => {
+                        self.eat_char();
+                        Ok(())
+                    }
+                    _ => Err(Error::TrailingCharacters),
+                }
+            }
             b',' => Err(Error::TrailingComma),
             _ => Err(Error::TrailingCharacters),
         }
@@ -210,48 +549,81 @@ impl<'a, 's> Deserializer<'a, 's> {
         }
 
         let start = self.index;
+        let max_end = self
+            .config
+            .max_string_len
+            .map_or(usize::MAX, |len| start.saturating_add(len));
+        // Whether the previous character was a backslash escaping the current one
+        let mut escaped = false;
         loop {
             match self.peek() {
-                Some(b'"') => {
-                    // Counts the number of backslashes in front of the current index.
-                    //
-                    // "some string with \\\" included."
-                    //                  ^^^^^
-                    //                  |||||
-                    //       loop run:  4321|
-                    //                      |
-                    //                   `index`
-                    //
-                    // Since we only get in this code branch if we found a " starting the string and `index` is greater
-                    // than the start position, we know the loop will end no later than this point.
-                    let leading_backslashes = |index: usize| -> usize {
-                        let mut count = 0;
-                        loop {
-                            if self.slice[index - count - 1] == b'\\' {
-                                count += 1;
-                            } else {
-                                return count;
-                            }
-                        }
-                    };
-
-                    let is_escaped = leading_backslashes(self.index) % 2 == 1;
-                    if is_escaped {
-                        self.eat_char(); // just continue
-                    } else {
-                        let end = self.index;
-                        self.eat_char();
+                Some(b'"') if !escaped => {
+                    let end = self.index;
+                    self.eat_char();
 
-                        return str::from_utf8(&self.slice[start..end])
-                            .map_err(|_| Error::InvalidUnicodeCodePoint);
-                    }
+                    return str::from_utf8(&self.slice[start..end])
+                        .map_err(|_| Error::InvalidUnicodeCodePoint);
+                }
+                Some(_) if self.index >= max_end => return Err(Error::StringTooLong),
+                Some(b'\\') => {
+                    escaped = !escaped;
+                    self.eat_char();
+                }
+                Some(_) => {
+                    escaped = false;
+                    self.eat_char();
                 }
-                Some(_) => self.eat_char(),
                 None => return Err(Error::EofWhileParsingString),
             }
         }
     }
 
+    /// Consumes a number, checking that it matches the JSON number grammar:
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+    fn skip_number(&mut self) -> Result<()> {
+        if self.peek() == Some(b'-') {
+            self.eat_char();
+        }
+
+        match self.next_char().ok_or(Error::EofWhileParsingNumber)? {
+            b'0' => {}
+            b'1'..=b'9' => {
+                while let Some(b'0'..=b'9') = self.peek() {
+                    self.eat_char();
+                }
+            }
+            _ => return Err(Error::InvalidNumber),
+        }
+
+        if self.peek() == Some(b'.') {
+            self.eat_char();
+            self.skip_digits()?;
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            self.eat_char();
+            if let Some(b'+' | b'-') = self.peek() {
+                self.eat_char();
+            }
+            self.skip_digits()?;
+        }
+
+        Ok(())
+    }
+
+    /// Consumes one or more decimal digits
+    fn skip_digits(&mut self) -> Result<()> {
+        match self.peek().ok_or(Error::EofWhileParsingNumber)? {
+            b'0'..=b'9' => {
+                while let Some(b'0'..=b'9') = self.peek() {
+                    self.eat_char();
+                }
+                Ok(())
+            }
+            _ => Err(Error::InvalidNumber),
+        }
+    }
+
     /// Consumes all the whitespace characters and returns a peek into the next character
     fn parse_whitespace(&mut self) -> Option<u8> {
         loop {
@@ -259,6 +631,10 @@ impl<'a, 's> Deserializer<'a, 's> {
                 Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') => {
                     self.eat_char();
                 }
+                Some(b'\x0C') | Some(b'\x0B') if self.config.lenient_whitespace => {
+                    self.eat_char();
+                }
+                Some(b'/') if self.config.allow_comments && self.skip_comment() => {}
                 other => {
                     return other;
                 }
@@ -266,6 +642,32 @@ impl<'a, 's> Deserializer<'a, 's> {
         }
     }
 
+    /// Skips the comment starting at the current `/`, returning `false` if there is none
+    ///
+    /// An unterminated block comment extends to the end of the input.
+    fn skip_comment(&mut self) -> bool {
+        match self.slice.get(self.index + 1) {
+            Some(b'/') => {
+                while !matches!(self.next_char(), None | Some(b'\n')) {}
+                true
+            }
+            Some(b'*') => {
+                self.index += 2;
+                loop {
+                    match self.next_char() {
+                        None => return true,
+                        Some(b'*') if self.peek() == Some(b'/') => {
+                            self.eat_char();
+                            return true;
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
     fn peek(&mut self) -> Option<u8> {
         self.slice.get(self.index).cloned()
     }
@@ -279,6 +681,7 @@ macro_rules! deserialize_unsigned {
         let peek = $self
             .parse_whitespace()
             .ok_or(Error::EofWhileParsingValue)?;
+        $self.count(Element::Scalar);
 
         match peek {
             b'-' => Err(Error::InvalidNumber),
@@ -321,6 +724,7 @@ macro_rules! deserialize_signed {
             }
             _ => false,
         };
+        $self.count(Element::Scalar);
 
         match $self.peek().ok_or(Error::EofWhileParsingValue)? {
             b'0' => {
@@ -351,26 +755,35 @@ macro_rules! deserialize_signed {
 }
 
 macro_rules! deserialize_fromstr {
-    ($self:ident, $visitor:ident, $typ:ident, $visit_fn:ident, $pattern:expr) => {{
-        match $self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
+    ($self:ident, $visitor:ident, $typ:ident, $visit_fn:ident) => {{
+        let peek = $self
+            .parse_whitespace()
+            .ok_or(Error::EofWhileParsingValue)?;
+        $self.count(Element::Scalar);
+
+        match peek {
             b'n' => {
                 $self.eat_char();
                 $self.parse_ident(b"ull")?;
                 $visitor.$visit_fn($typ::NAN)
             }
+            b'"' if $self.config.quoted_non_finite => match $self.parse_str()? {
+                "NaN" => $visitor.$visit_fn($typ::NAN),
+                "Infinity" => $visitor.$visit_fn($typ::INFINITY),
+                "-Infinity" => $visitor.$visit_fn($typ::NEG_INFINITY),
+                _ => Err(Error::InvalidNumber),
+            },
             _ => {
                 let start = $self.index;
-                while $self.peek().is_some() {
-                    let c = $self.peek().unwrap();
-                    if $pattern.iter().find(|&&d| d == c).is_some() {
-                        $self.eat_char();
-                    } else {
-                        break;
-                    }
+                $self.skip_number().or(Err(Error::InvalidNumber))?;
+
+                // Reject malformed numbers like `1+2` as a whole instead of parsing their valid
+                // prefix and leaving the rest
+                if let Some(b'0'..=b'9' | b'+' | b'-' | b'.' | b'e' | b'E') = $self.peek() {
+                    return Err(Error::InvalidNumber);
                 }
 
-                // Note(unsafe): We already checked that it only contains ascii. This is only true if the
-                // caller has guaranteed that `pattern` contains only ascii characters.
+                // Note(unsafe): `skip_number` only consumes ASCII characters
                 let s = unsafe { str::from_utf8_unchecked(&$self.slice[start..$self.index]) };
 
                 let v = $typ::from_str(s).or(Err(Error::InvalidNumber))?;
@@ -384,12 +797,42 @@ macro_rules! deserialize_fromstr {
 impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
     type Error = Error;
 
-    /// Unsupported. Can’t parse a value without knowing its expected type.
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    /// Deserializes whatever value comes next, dispatching on its first character.
+    ///
+    /// Integers are passed to the visitor as `u64` or, if negative, `i64`; numbers with a fraction
+    /// or exponent and integers that don't fit are passed as `f64`.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::AnyIsUnsupported)
+        match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
+            b'"' => self.deserialize_str(visitor),
+            b'[' => self.deserialize_seq(visitor),
+            b'{' => self.deserialize_map(visitor),
+            b't' | b'f' => self.deserialize_bool(visitor),
+            b'n' => self.deserialize_unit(visitor),
+            b'-' | b'0'..=b'9' => {
+                self.count(Element::Scalar);
+                let start = self.index;
+                self.skip_number()?;
+                let number = &self.slice[start..self.index];
+                // `skip_number` only accepts ASCII
+                let number = str::from_utf8(number).map_err(|_| Error::InvalidNumber)?;
+
+                if !number.contains(['.', 'e', 'E']) {
+                    if number.starts_with('-') {
+                        if let Ok(n) = number.parse::<i64>() {
+                            return visitor.visit_i64(n);
+                        }
+                    } else if let Ok(n) = number.parse::<u64>() {
+                        return visitor.visit_u64(n);
+                    }
+                }
+
+                visitor.visit_f64(number.parse::<f64>().map_err(|_| Error::InvalidNumber)?)
+            }
+            _ => Err(Error::ExpectedSomeValue),
+        }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -397,6 +840,7 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         V: Visitor<'de>,
     {
         let peek = self.parse_whitespace().ok_or(Error::EofWhileParsingValue)?;
+        self.count(Element::Scalar);
 
         match peek {
             b't' => {
@@ -473,63 +917,36 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
     where
         V: Visitor<'de>,
     {
-        deserialize_fromstr!(self, visitor, f32, visit_f32, b"0123456789+-.eE")
+        deserialize_fromstr!(self, visitor, f32, visit_f32)
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        deserialize_fromstr!(self, visitor, f64, visit_f64, b"0123456789+-.eE")
+        deserialize_fromstr!(self, visitor, f64, visit_f64)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        match self.parse_whitespace() {
+            Some(b'0'..=b'9') if self.config.char_from_code_point => {
+                let code_point = <u32 as de::Deserialize>::deserialize(&mut *self)?;
+                visitor
+                    .visit_char(char::from_u32(code_point).ok_or(Error::InvalidUnicodeCodePoint)?)
+            }
+            _ => self.deserialize_str(visitor),
+        }
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let escaped_string = self.parse_str()?;
-
-        // If the unescape buffer is not provided, skip unescaping strings
-        let Some(string_unescape_buffer) = self.string_unescape_buffer.as_deref_mut() else {
-            return visitor.visit_borrowed_str(escaped_string);
-        };
-
-        // If the escaped string doesn't contain '\\', it' can't have any escaped characters
-        if !escaped_string.as_bytes().contains(&b'\\') {
-            return visitor.visit_borrowed_str(escaped_string);
-        }
-
-        let mut string_unescape_buffer_write_position = 0;
-
-        for fragment in crate::str::EscapedStr(escaped_string).fragments() {
-            let char_encode_buffer = &mut [0; 4];
-
-            let unescaped_bytes = match fragment? {
-                crate::str::EscapedStringFragment::NotEscaped(fragment) => fragment.as_bytes(),
-                crate::str::EscapedStringFragment::Escaped(c) => {
-                    c.encode_utf8(char_encode_buffer).as_bytes()
-                }
-            };
-
-            string_unescape_buffer[string_unescape_buffer_write_position..]
-                .get_mut(..unescaped_bytes.len())
-                .ok_or(Error::EscapedStringIsTooLong)?
-                .copy_from_slice(unescaped_bytes);
-
-            string_unescape_buffer_write_position += unescaped_bytes.len();
-        }
-
-        visitor.visit_str(
-            str::from_utf8(&string_unescape_buffer[..string_unescape_buffer_write_position])
-                .map_err(|_| Error::InvalidUnicodeCodePoint)?,
-        )
+        self.count(Element::Scalar);
+        self.parse_and_visit_str(visitor)
     }
 
     /// Unsupported. String is not available in no-std.
@@ -540,20 +957,40 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         self.deserialize_str(visitor)
     }
 
-    /// Unsupported
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
+    /// Deserializes an array of integers like `[1, 2, 3]` as bytes
+    ///
+    /// The elements aren't contiguous in the input, so they are copied into the buffer used to
+    /// unescape strings and passed to the visitor from there, without borrowing from the input.
+    /// Without such a buffer this is unsupported.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::BytesIsUnsupported)
+        if self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? != b'[' {
+            return Err(Error::InvalidType);
+        }
+        let scratch = self
+            .string_unescape_buffer
+            .take()
+            .ok_or(Error::BytesIsUnsupported)?;
+        self.eat_char();
+        self.enter()?;
+        self.count(Element::Array);
+
+        let result = self.parse_bytes(scratch).and_then(|len| {
+            self.end_seq()?;
+            visitor.visit_bytes(&scratch[..len])
+        });
+        self.string_unescape_buffer = Some(scratch);
+        result
     }
 
-    /// Unsupported
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    /// See [`deserialize_bytes`](#method.deserialize_bytes)
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::BytesIsUnsupported)
+        self.deserialize_bytes(visitor)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
@@ -563,9 +1000,12 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
             b'n' => {
                 self.eat_char();
+                self.count(Element::Scalar);
                 self.parse_ident(b"ull")?;
                 visitor.visit_none()
             }
+            // Each level of `Option<Option<..>>` recurses once, so the depth is bounded by the type
+            // and not by the input
             _ => visitor.visit_some(self),
         }
     }
@@ -584,6 +1024,7 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         match peek {
             b'n' => {
                 self.eat_char();
+                self.count(Element::Scalar);
                 self.parse_ident(b"ull")?;
                 visitor.visit_unit()
             }
@@ -618,6 +1059,7 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
                     // The only structure which is deserialized at this point is an `EscapedStr`,
                     // so pass the escaped string to its implementation of visit_borrowed_str.
                     // This line defacto becomes `Ok(EscapedStr(self.0.parse_str()?))`.
+                    self.0.count(Element::Scalar);
                     visitor.visit_borrowed_str(self.0.parse_str()?)
                 }
 
@@ -630,6 +1072,10 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
             }
 
             visitor.visit_newtype_struct(EscapedStringDeserializer(self))
+        } else if name == crate::str::RawValue::NAME {
+            // If the newtype struct is a `RawValue`, skip the value and pass on its text
+            let raw = self.parse_raw()?;
+            visitor.visit_newtype_struct(de::value::BorrowedStrDeserializer::new(raw))
         } else {
             visitor.visit_newtype_struct(self)
         }
@@ -642,6 +1088,8 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
             b'[' => {
                 self.eat_char();
+                self.enter()?;
+                self.count(Element::Array);
                 let ret = visitor.visit_seq(SeqAccess::new(self))?;
 
                 self.end_seq()?;
@@ -656,19 +1104,44 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match self.parse_whitespace() {
+            Some(b'{') if self.config.tuple_from_object => {
+                self.eat_char();
+                self.enter()?;
+                self.count(Element::Object);
+                let ret = visitor.visit_seq(IndexedObjectAccess::new(self))?;
+
+                self.end_map()?;
+
+                Ok(ret)
+            }
+            Some(b'[') if self.config.tuple_prefix => {
+                self.eat_char();
+                self.enter()?;
+                self.count(Element::Array);
+                let mut seq = SeqAccess::new(self);
+                let ret = visitor.visit_seq(&mut seq)?;
+
+                // Skip the elements after the ones the tuple has
+                while de::SeqAccess::next_element::<de::IgnoredAny>(&mut seq)?.is_some() {}
+                self.end_seq()?;
+
+                Ok(ret)
+            }
+            _ => self.deserialize_seq(visitor),
+        }
     }
 
     fn deserialize_tuple_struct<V>(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.deserialize_tuple(len, visitor)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
@@ -679,6 +1152,8 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
 
         if peek == b'{' {
             self.eat_char();
+            self.enter()?;
+            self.count(Element::Object);
 
             let ret = visitor.visit_map(MapAccess::new(self))?;
 
@@ -712,13 +1187,23 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         V: Visitor<'de>,
     {
         match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
-            b'"' => visitor.visit_enum(UnitVariantAccess::new(self)),
+            b'"' => {
+                self.count(Element::Scalar);
+                visitor.visit_enum(UnitVariantAccess::new(self))
+            }
+            b'0'..=b'9' if self.config.enum_from_index => {
+                let index = <u32 as de::Deserialize>::deserialize(&mut *self)?;
+                visitor.visit_enum(de::value::U32Deserializer::new(index))
+            }
             b'{' => {
                 self.eat_char();
+                self.enter()?;
+                self.count(Element::Object);
                 let value = visitor.visit_enum(VariantAccess::new(self))?;
                 match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
                     b'}' => {
                         self.eat_char();
+                        self.depth -= 1;
                         Ok(value)
                     }
                     _ => Err(Error::ExpectedSomeValue),
@@ -728,11 +1213,12 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
         }
     }
 
+    /// Identifiers are either object keys or part of an enum, which is counted by itself
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        self.parse_and_visit_str(visitor)
     }
 
     /// Used to throw out fields from JSON objects that we don’t want to
@@ -741,23 +1227,19 @@ impl<'a, 'de, 's> de::Deserializer<'de> for &'a mut Deserializer<'de, 's> {
     where
         V: Visitor<'de>,
     {
+        if self.member.is_some() && self.collect_extra()? {
+            return visitor.visit_unit();
+        }
+
         match self.parse_whitespace().ok_or(Error::EofWhileParsingValue)? {
-            b'"' => self.deserialize_str(visitor),
             b'[' => self.deserialize_seq(visitor),
-            b'{' => self.deserialize_struct("ignored", &[], visitor),
-            b',' | b'}' | b']' => Err(Error::ExpectedSomeValue),
-            // If it’s something else then we chomp until we get to an end delimiter.
-            // This does technically allow for illegal JSON since we’re just ignoring
-            // characters rather than parsing them.
-            _ => loop {
-                match self.peek() {
-                    // The visitor is expected to be UnknownAny’s visitor, which
-                    // implements visit_unit to return its unit Ok result.
-                    Some(b',') | Some(b'}') | Some(b']') => break visitor.visit_unit(),
-                    Some(_) => self.eat_char(),
-                    None => break Err(Error::EofWhileParsingString),
-                }
-            },
+            b'{' => self.deserialize_map(visitor),
+            _ => {
+                self.skip_scalar()?;
+                // The visitor is expected to be IgnoredAny’s visitor, which
+                // implements visit_unit to return its unit Ok result.
+                visitor.visit_unit()
+            }
         }
     }
 }
@@ -776,11 +1258,30 @@ impl de::Error for Error {
         {
             use core::fmt::Write;
 
-            let mut string = heapless::String::new();
-            write!(string, "{:.64}", msg).unwrap();
-            Error::CustomErrorWithMessage(string)
+            /// Keeps as much of the message as fits, since `Display` implementations don't
+            /// necessarily respect a precision
+            struct Truncate(heapless::String<CUSTOM_ERROR_MESSAGE_LEN>);
+
+            impl Write for Truncate {
+                fn write_str(&mut self, s: &str) -> fmt::Result {
+                    for c in s.chars() {
+                        self.0.push(c).map_err(|_| fmt::Error)?;
+                    }
+                    Ok(())
+                }
+            }
+
+            let mut string = Truncate(heapless::String::new());
+            // Formatting stops with an error once the message is full
+            let _ = write!(string, "{}", msg);
+            Error::CustomErrorWithMessage(string.0)
         }
     }
+
+    #[cfg(not(feature = "custom-error-messages"))]
+    fn invalid_length(_len: usize, _exp: &dyn de::Expected) -> Self {
+        Error::InvalidLength
+    }
 }
 
 impl fmt::Display for Error {
@@ -809,6 +1310,12 @@ impl fmt::Display for Error {
                      `null`."
                 }
                 Error::ExpectedSomeValue => "Expected this character to start a JSON value.",
+                Error::ChunksTooLong => "Input chunks don't fit into the scratch buffer.",
+                Error::ByteArrayIsTooLong => "Array of bytes doesn't fit into the scratch buffer.",
+                Error::StringTooLong => "String is longer than allowed.",
+                Error::TooManyElements => "Array or object has more elements than allowed.",
+                Error::RecursionLimitExceeded => "Arrays and objects are nested too deeply.",
+                Error::InvalidLength => "Invalid number of elements.",
                 Error::InvalidNumber => "Invalid number.",
                 Error::InvalidType => "Invalid type",
                 Error::InvalidUnicodeCodePoint => "Invalid unicode code point.",
@@ -828,6 +1335,76 @@ impl fmt::Display for Error {
     }
 }
 
+impl Error {
+    /// Display the error together with the `position` in the input it occurred at, like
+    /// `InvalidType at byte 12`
+    ///
+    /// ```
+    /// # use serde::Deserialize;
+    /// let mut de = serde_json_core::de::Deserializer::new(br#"{"led": "on"}"#, None);
+    /// let error = bool::deserialize(&mut de).unwrap_err();
+    /// assert_eq!(format!("{}", error.at(de.position())), "InvalidType at byte 0");
+    /// ```
+    pub fn at(&self, position: usize) -> ErrorAt<'_> {
+        ErrorAt {
+            error: self,
+            position,
+        }
+    }
+
+    /// Whether the input ended before the value did, for any of the `EofWhileParsing*` errors
+    ///
+    /// When receiving JSON in pieces, this tells waiting for more bytes apart from giving up on
+    /// malformed input. The exceptions are a `true`, `false` or `null` cut short, which fails with
+    /// [`Error::ExpectedSomeIdent`], and a float cut short like `1e`, which fails with
+    /// [`Error::InvalidNumber`], just like malformed ones.
+    pub fn is_eof(&self) -> bool {
+        matches!(
+            self,
+            Error::EofWhileParsingList
+                | Error::EofWhileParsingObject
+                | Error::EofWhileParsingString
+                | Error::EofWhileParsingNumber
+                | Error::EofWhileParsingValue
+        )
+    }
+}
+
+/// Displays an [`Error`] with its position in the input, see [`Error::at`]
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorAt<'a> {
+    error: &'a Error,
+    position: usize,
+}
+
+impl fmt::Display for ErrorAt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at byte {}", self.error, self.position)
+    }
+}
+
+/// The number of values parsed by a [`Deserializer`], see [`from_slice_with_stats`]
+///
+/// Every value is counted, including the elements of arrays and the values of object members,
+/// but not object keys. Values that are skipped, like unknown struct fields, are counted too.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// Objects, including enum variants with data like `{"Variant":1}`
+    pub objects: usize,
+    /// Arrays
+    pub arrays: usize,
+    /// Strings, numbers, booleans and nulls
+    pub scalars: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Element {
+    Object,
+    Array,
+    Scalar,
+}
+
 fn from_slice_maybe_escaped<'a, T>(
     v: &'a [u8],
     string_unescape_buffer: Option<&mut [u8]>,
@@ -835,9 +1412,20 @@ fn from_slice_maybe_escaped<'a, T>(
 where
     T: de::Deserialize<'a>,
 {
-    let mut de = Deserializer::new(v, string_unescape_buffer);
-    let value = de::Deserialize::deserialize(&mut de)?;
-    let length = de.end()?;
+    from_slice_maybe_escaped_with_config(v, string_unescape_buffer, &Config::default())
+}
+
+fn from_slice_maybe_escaped_with_config<'a, T>(
+    v: &'a [u8],
+    string_unescape_buffer: Option<&mut [u8]>,
+    config: &Config,
+) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut de = Deserializer::new(v, string_unescape_buffer).with_config(config.clone());
+    let value = de::Deserialize::deserialize(&mut de)?;
+    let length = de.end()?;
 
     Ok((value, length))
 }
@@ -854,6 +1442,61 @@ where
     from_slice_maybe_escaped(v, Some(string_unescape_buffer))
 }
 
+/// Deserializes an instance of type `T` from bytes of JSON text, borrowing strings without escape
+/// sequences from `v` and unescaping the others into `scratch`
+///
+/// Unlike [`from_slice_escaped`], unescaped strings are borrowed from `scratch` for as long as `v`,
+/// so `&str` fields work whether a string has escape sequences or not. Each unescaped string takes
+/// up its own part of `scratch`; a `scratch` as long as `v` always suffices.
+/// Returns the value and the number of bytes consumed in the process
+pub fn from_slice_auto<'a, T>(v: &'a [u8], scratch: &'a mut [u8]) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut de = Deserializer::new(v, None);
+    de.scratch = Some(scratch);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    let length = de.end()?;
+
+    Ok((value, length))
+}
+
+/// Deserializes an instance of type `T` from bytes of JSON text, calling `extras` with the key and
+/// text of every object member that a struct skips, see [`Deserializer::with_extras`]
+///
+/// This keeps unknown members around, e.g. to pass them on unchanged:
+///
+/// ```
+/// # use heapless::FnvIndexMap;
+/// # use serde_json_core::str::RawValue;
+/// #[derive(serde::Deserialize)]
+/// struct Settings {
+///     volume: u8,
+/// }
+///
+/// let mut extras = FnvIndexMap::<&str, RawValue<'_>, 4>::new();
+/// let json = br#"{"volume":7,"theme":{"dark":true},"tags":["a"]}"#;
+/// let (settings, _) = serde_json_core::de::from_slice_with_extras::<Settings, _>(json, |key, raw| {
+///     extras.insert(key, raw).ok();
+/// })
+/// .unwrap();
+/// assert_eq!(settings.volume, 7);
+/// assert_eq!(extras["theme"].get(), r#"{"dark":true}"#);
+/// assert_eq!(extras["tags"].get(), r#"["a"]"#);
+/// ```
+/// Returns the value and the number of bytes consumed in the process
+pub fn from_slice_with_extras<'a, T, F>(v: &'a [u8], mut extras: F) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+    F: FnMut(&'a str, RawValue<'a>),
+{
+    let mut de = Deserializer::new(v, None).with_extras(&mut extras);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    let length = de.end()?;
+
+    Ok((value, length))
+}
+
 /// Deserializes an instance of type `T` from bytes of JSON text
 /// Returns the value and the number of bytes consumed in the process
 pub fn from_slice<'a, T>(v: &'a [u8]) -> Result<(T, usize)>
@@ -863,6 +1506,163 @@ where
     from_slice_maybe_escaped(v, None)
 }
 
+/// Deserializes an instance of type `T` from bytes of JSON text, discarding the number of bytes
+/// consumed
+pub fn from_slice_value<'a, T>(v: &'a [u8]) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice(v).map(|(value, _)| value)
+}
+
+/// Deserializes an instance of type `T` from bytes of JSON text followed by any number of NUL
+/// bytes, like a JSON string in a zero-initialized buffer
+///
+/// Returns the value and the length of the JSON text without the padding
+pub fn from_slice_trim_nul<'a, T>(v: &'a [u8]) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    let len = v.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+    from_slice(&v[..len])
+}
+
+/// Maximum nesting of arrays and objects accepted by [`validate`]
+///
+/// Every level takes a stack frame for each of a few functions. Their size mostly depends on the
+/// size of [`Error`], which grows with the `custom-error-messages` features.
+pub const VALIDATE_MAX_DEPTH: usize = 128;
+
+/// Checks that `v` holds exactly one well-formed JSON value, optionally surrounded by whitespace,
+/// without deserializing it into any type
+///
+/// Arrays and objects nested deeper than [`VALIDATE_MAX_DEPTH`] fail with
+/// [`Error::RecursionLimitExceeded`], so the stack usage is bounded for any input.
+/// Returns the number of bytes consumed in the process
+pub fn validate(v: &[u8]) -> Result<usize> {
+    let config = Config::default().max_depth(VALIDATE_MAX_DEPTH);
+    from_slice_with_config::<de::IgnoredAny>(v, &config).map(|(_, length)| length)
+}
+
+/// Splits the `"key": value` object member off the start of `v`, returning its key, still
+/// escaped, and the length of the member
+pub(crate) fn split_member(v: &[u8]) -> Option<(&str, usize)> {
+    let mut de = Deserializer::new(v, None);
+    let key = de.parse_str().ok()?;
+    de.parse_object_colon().ok()?;
+    <de::IgnoredAny as de::Deserialize<'_>>::deserialize(&mut de).ok()?;
+    Some((key, de.index))
+}
+
+/// What follows the parsed part of a buffer, see [`trailing`]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Trailing {
+    /// The whole buffer was consumed
+    Nothing,
+    /// Only JSON whitespace is left
+    Whitespace,
+    /// Something other than whitespace is left, e.g. the start of the next message
+    Data,
+}
+
+/// Classifies what is left of `buf` after the first `consumed` bytes were parsed
+///
+/// Framing code can use this to decide whether the remaining bytes belong to another message.
+pub fn trailing(buf: &[u8], consumed: usize) -> Trailing {
+    let rest = buf.get(consumed..).unwrap_or(&[]);
+    if rest.is_empty() {
+        Trailing::Nothing
+    } else if rest
+        .iter()
+        .all(|c| matches!(c, b' ' | b'\n' | b'\t' | b'\r'))
+    {
+        Trailing::Whitespace
+    } else {
+        Trailing::Data
+    }
+}
+
+/// Deserializes an instance of type `T` from JSON text split into several chunks, e.g. the two
+/// halves of a wrapped ring buffer
+///
+/// If all of the text is in a single chunk, it's parsed in place. Otherwise the chunks are copied
+/// into `scratch` first, and `Error::ChunksTooLong` is returned if they don't fit. Borrowed strings
+/// point into whichever buffer was parsed.
+/// Returns the value and the number of bytes consumed in the process
+pub fn from_chunks<'a, T>(chunks: &[&'a [u8]], scratch: &'a mut [u8]) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut non_empty = chunks.iter().filter(|chunk| !chunk.is_empty());
+    match (non_empty.next(), non_empty.next()) {
+        (None, _) => from_slice(&[]),
+        (Some(chunk), None) => from_slice(chunk),
+        _ => {
+            let mut len = 0;
+            for chunk in chunks {
+                scratch
+                    .get_mut(len..len + chunk.len())
+                    .ok_or(Error::ChunksTooLong)?
+                    .copy_from_slice(chunk);
+                len += chunk.len();
+            }
+            from_slice(&scratch[..len])
+        }
+    }
+}
+
+/// Deserializes an instance of type `T` from bytes of JSON text, counting the values it contains
+///
+/// This helps spotting unusually large messages.
+/// Returns the value, the number of bytes consumed in the process and the counts
+pub fn from_slice_with_stats<'a, T>(v: &'a [u8]) -> Result<(T, usize, Stats)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut de = Deserializer::new(v, None).with_stats();
+    let value = de::Deserialize::deserialize(&mut de)?;
+    let length = de.end()?;
+
+    Ok((value, length, de.stats.unwrap_or_default()))
+}
+
+/// Deserializes an instance of type `T` from bytes of JSON text, skipping the members of the
+/// outermost object whose keys aren't in `fields`
+///
+/// The skipped members are only checked to be well-formed, and never reach the `Deserialize`
+/// implementation of `T`, so e.g. struct fields missing from `fields` must be optional. Keys are
+/// compared as written, without unescaping them.
+/// Returns the value and the number of bytes consumed in the process
+pub fn from_slice_fields<'a, T>(v: &'a [u8], fields: &[&str]) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut de = Deserializer::new(v, None).with_field_mask(fields);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    let length = de.end()?;
+
+    Ok((value, length))
+}
+
+/// Deserializes an instance of type `T` from bytes of JSON text, also returning where the value is
+///
+/// The span is the byte range `[start, end)` of the value in `v`, without the whitespace around it.
+pub fn from_slice_spanned<'a, T>(v: &'a [u8]) -> Result<(T, Range<usize>)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut de = Deserializer::new(v, None);
+    // An empty input still fails below, when deserializing the value
+    de.parse_whitespace();
+    let start = de.index;
+    let value = de::Deserialize::deserialize(&mut de)?;
+    let end = de.index;
+    de.end()?;
+
+    Ok((value, start..end))
+}
+
 /// Deserializes an instance of type T from a string of JSON text, using the provided buffer to unescape strings
 pub fn from_str_escaped<'a, T>(s: &'a str, string_unescape_buffer: &mut [u8]) -> Result<(T, usize)>
 where
@@ -871,6 +1671,25 @@ where
     from_slice_escaped(s.as_bytes(), string_unescape_buffer)
 }
 
+/// Deserializes an instance of type T from a string of JSON text, borrowing strings without escape
+/// sequences and unescaping the others into `scratch`, see [`from_slice_auto`]
+pub fn from_str_auto<'a, T>(s: &'a str, scratch: &'a mut [u8]) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice_auto(s.as_bytes(), scratch)
+}
+
+/// Deserializes an instance of type T from a string of JSON text, calling `extras` with the key and
+/// text of every object member that a struct skips, see [`from_slice_with_extras`]
+pub fn from_str_with_extras<'a, T, F>(s: &'a str, extras: F) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+    F: FnMut(&'a str, RawValue<'a>),
+{
+    from_slice_with_extras(s.as_bytes(), extras)
+}
+
 /// Deserializes an instance of type T from a string of JSON text
 pub fn from_str<'a, T>(s: &'a str) -> Result<(T, usize)>
 where
@@ -879,6 +1698,54 @@ where
     from_slice(s.as_bytes())
 }
 
+/// Deserializes an instance of type T from a string of JSON text, discarding the number of bytes
+/// consumed
+pub fn from_str_value<'a, T>(s: &'a str) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice_value(s.as_bytes())
+}
+
+/// Deserializes an instance of type `T` from bytes of JSON text, accepting the extensions enabled in
+/// `config`
+/// Returns the value and the number of bytes consumed in the process
+pub fn from_slice_with_config<'a, T>(v: &'a [u8], config: &Config) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice_maybe_escaped_with_config(v, None, config)
+}
+
+/// Deserializes an instance of type `T` from a string of JSON text, counting the values it contains
+/// Returns the value, the number of bytes consumed in the process and the counts
+pub fn from_str_with_stats<'a, T>(s: &'a str) -> Result<(T, usize, Stats)>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice_with_stats(s.as_bytes())
+}
+
+/// Deserializes an instance of type `T` from a string of JSON text, also returning where the value is
+///
+/// See [`from_slice_spanned`].
+pub fn from_str_spanned<'a, T>(s: &'a str) -> Result<(T, Range<usize>)>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice_spanned(s.as_bytes())
+}
+
+/// Deserializes an instance of type `T` from a string of JSON text, accepting the extensions enabled
+/// in `config`
+/// Returns the value and the number of bytes consumed in the process
+pub fn from_str_with_config<'a, T>(s: &'a str, config: &Config) -> Result<(T, usize)>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice_with_config(s.as_bytes(), config)
+}
+
 #[cfg(test)]
 mod tests {
     use serde_derive::Deserialize;
@@ -902,6 +1769,35 @@ mod tests {
         assert!(crate::from_str::<[i32; 2]>("[0, 1,]").is_err());
     }
 
+    #[test]
+    fn heapless_vec() {
+        type V = heapless::Vec<u8, 3>;
+
+        assert_eq!(
+            crate::from_str::<V>("[1, 2, 3]"),
+            Ok((V::from_slice(&[1, 2, 3]).unwrap(), 9))
+        );
+        assert_eq!(
+            crate::from_str::<V>("[1, 2]"),
+            Ok((V::from_slice(&[1, 2]).unwrap(), 6))
+        );
+        assert_eq!(crate::from_str::<V>("[]"), Ok((V::new(), 2)));
+
+        // one element more than the capacity
+        #[cfg(not(feature = "custom-error-messages"))]
+        assert_eq!(
+            crate::from_str::<V>("[1, 2, 3, 4]"),
+            Err(crate::de::Error::InvalidLength)
+        );
+        #[cfg(feature = "custom-error-messages")]
+        assert_eq!(
+            crate::from_str::<V>("[1, 2, 3, 4]"),
+            Err(crate::de::Error::CustomErrorWithMessage(
+                "invalid length 4, expected a sequence".parse().unwrap()
+            ))
+        );
+    }
+
     #[test]
     fn bool() {
         assert_eq!(crate::from_str("true"), Ok((true, 4)));
@@ -919,11 +1815,53 @@ mod tests {
 
     #[test]
     fn floating_point() {
+        use core::fmt::Write;
+
         assert_eq!(crate::from_str("5.0"), Ok((5.0, 3)));
         assert_eq!(crate::from_str("1"), Ok((1.0, 1)));
         assert_eq!(crate::from_str("1e5"), Ok((1e5, 3)));
         assert!(crate::from_str::<f32>("a").is_err());
         assert!(crate::from_str::<f32>(",").is_err());
+
+        // Malformed numbers are rejected as a whole
+        for s in [
+            "1+2", "1-2", "--1", "1e1e1", "1.2.3", "01", "+1", ".5", "1.",
+        ] {
+            assert_eq!(
+                crate::from_str::<f32>(s),
+                Err(crate::de::Error::InvalidNumber)
+            );
+            let mut array = heapless::String::<8>::new();
+            write!(array, "[{}]", s).unwrap();
+            assert_eq!(
+                crate::from_str::<[f64; 1]>(&array),
+                Err(crate::de::Error::InvalidNumber)
+            );
+        }
+
+        // Exponents need mantissa and exponent digits
+        for s in ["e5", "E5", "1e", "1E", "1e+", "1e-", "-e5", "1.e5"] {
+            assert_eq!(
+                crate::from_str::<f32>(s),
+                Err(crate::de::Error::InvalidNumber)
+            );
+            assert_eq!(
+                crate::from_str::<f64>(s),
+                Err(crate::de::Error::InvalidNumber)
+            );
+        }
+        assert_eq!(crate::from_str("1e+5"), Ok((1e5f32, 4)));
+        assert_eq!(crate::from_str("1E-5"), Ok((1e-5f64, 4)));
+
+        // A number is complete before anything else
+        assert_eq!(
+            crate::from_str::<f32>("1e5foo"),
+            Err(crate::de::Error::TrailingCharacters)
+        );
+        assert_eq!(
+            crate::from_str::<(f32, f32)>("[1e5,-2]"),
+            Ok(((1e5, -2.0), 8))
+        );
     }
 
     #[test]
@@ -935,6 +1873,46 @@ mod tests {
         assert!(crate::from_str::<f32>(",").is_err());
     }
 
+    #[test]
+    fn signed_integer_range() {
+        macro_rules! check {
+            ($ixx:ident, $over_max:expr, $under_min:expr) => {
+                let max = crate::to_string::<_, 32>(&$ixx::MAX).unwrap();
+                let min = crate::to_string::<_, 32>(&$ixx::MIN).unwrap();
+                assert_eq!(crate::from_str(&max), Ok(($ixx::MAX, max.len())));
+                assert_eq!(crate::from_str(&min), Ok(($ixx::MIN, min.len())));
+                assert_eq!(
+                    crate::from_str::<$ixx>($over_max),
+                    Err(crate::de::Error::InvalidNumber)
+                );
+                assert_eq!(
+                    crate::from_str::<$ixx>($under_min),
+                    Err(crate::de::Error::InvalidNumber)
+                );
+            };
+        }
+
+        check!(i8, "128", "-129");
+        check!(i16, "32768", "-32769");
+        check!(i32, "2147483648", "-2147483649");
+        check!(i64, "9223372036854775808", "-9223372036854775809");
+
+        assert_eq!(crate::from_str("-2147483648"), Ok((i32::MIN, 11)));
+        assert_eq!(crate::from_str("-9223372036854775808"), Ok((i64::MIN, 20)));
+
+        // Negative numbers are accumulated as negative values, so `MIN` doesn't overflow before
+        // its last digit, also when followed by more input
+        assert_eq!(
+            crate::from_str("[-128,-32768,-2147483648,-9223372036854775808]"),
+            Ok(((i8::MIN, i16::MIN, i32::MIN, i64::MIN), 46))
+        );
+        assert_eq!(
+            crate::from_str::<(i8, i8)>("[-128,-1280]"),
+            Err(crate::de::Error::InvalidNumber)
+        );
+        assert_eq!(crate::from_str("-0"), Ok((0i8, 2)));
+    }
+
     #[test]
     fn enum_clike() {
         assert_eq!(crate::from_str(r#" "boolean" "#), Ok((Type::Boolean, 11)));
@@ -943,500 +1921,1581 @@ mod tests {
     }
 
     #[test]
-    fn char() {
-        fn from_str_test<'de, T: serde::Deserialize<'de>>(
-            s: &'de str,
-        ) -> super::Result<(T, usize)> {
-            crate::from_str_escaped(s, &mut [0; 8])
-        }
+    fn enum_from_index() {
+        use crate::de::{Config, Error};
 
-        assert_eq!(from_str_test(r#""n""#), Ok(('n', 3)));
-        assert_eq!(from_str_test(r#""\"""#), Ok(('"', 4)));
-        assert_eq!(from_str_test(r#""\\""#), Ok(('\\', 4)));
-        assert_eq!(from_str_test(r#""/""#), Ok(('/', 3)));
-        assert_eq!(from_str_test(r#""\b""#), Ok(('\x08', 4)));
-        assert_eq!(from_str_test(r#""\f""#), Ok(('\x0C', 4)));
-        assert_eq!(from_str_test(r#""\n""#), Ok(('\n', 4)));
-        assert_eq!(from_str_test(r#""\r""#), Ok(('\r', 4)));
-        assert_eq!(from_str_test(r#""\t""#), Ok(('\t', 4)));
-        assert_eq!(from_str_test(r#""\u000b""#), Ok(('\x0B', 8)));
-        assert_eq!(from_str_test(r#""\u000B""#), Ok(('\x0B', 8)));
-        assert_eq!(from_str_test(r#""Σ""#), Ok(('Σ', 4)));
+        let config = Config::default().enum_from_index(true);
+        assert_eq!(
+            crate::from_str_with_config(r#" "number" "#, &config),
+            Ok((Type::Number, 10))
+        );
+        assert_eq!(
+            crate::from_str_with_config(" 1 ", &config),
+            Ok((Type::Number, 3))
+        );
+        assert_eq!(
+            crate::from_str_with_config(r#"[0,"thing",2]"#, &config),
+            Ok(([Type::Boolean, Type::Thing, Type::Thing], 13))
+        );
+        assert!(crate::from_str_with_config::<Type>("3", &config).is_err());
+
+        assert_eq!(crate::from_str::<Type>("1"), Err(Error::ExpectedSomeValue));
     }
 
     #[test]
-    fn str() {
-        // No escaping, so can borrow from the input
-        assert_eq!(crate::from_str(r#" "hello" "#), Ok(("hello", 9)));
-        assert_eq!(crate::from_str(r#" "" "#), Ok(("", 4)));
-        assert_eq!(crate::from_str(r#" " " "#), Ok((" ", 5)));
-        assert_eq!(crate::from_str(r#" "👏" "#), Ok(("👏", 8)));
-
-        fn s(s: &'static str) -> heapless::String<1024> {
-            s.parse().expect("Failed to create test string")
-        }
+    fn stats() {
+        use crate::de::{Deserializer, Stats};
 
-        fn from_str_test<'de, T: serde::Deserialize<'de>>(
-            s: &'de str,
-        ) -> super::Result<(T, usize)> {
-            crate::from_str_escaped(s, &mut [0; 16])
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Reading<'a> {
+            sensor: &'a str,
+            values: [i32; 3],
+            flags: (bool, Option<u8>),
         }
 
-        // escaped " in the string content
-        assert_eq!(from_str_test(r#" "foo\"bar" "#), Ok((s(r#"foo"bar"#), 12)));
+        let json = r#"{"sensor":"t1","values":[1,-2,3],"flags":[true,null],"extra":{"a":[]}}"#;
+        let (reading, len, stats) = crate::de::from_str_with_stats::<Reading<'_>>(json).unwrap();
+        assert_eq!(len, json.len());
+        assert_eq!(reading.values, [1, -2, 3]);
         assert_eq!(
-            from_str_test(r#" "foo\\\"bar" "#),
-            Ok((s(r#"foo\"bar"#), 14))
+            stats,
+            Stats {
+                objects: 2,
+                arrays: 3,
+                scalars: 6,
+            }
         );
+
         assert_eq!(
-            from_str_test(r#" "foo\"\"bar" "#),
-            Ok((s(r#"foo""bar"#), 14))
+            crate::de::from_str_with_stats::<[Type; 2]>(r#"["boolean","thing"]"#),
+            Ok((
+                [Type::Boolean, Type::Thing],
+                19,
+                Stats {
+                    objects: 0,
+                    arrays: 1,
+                    scalars: 2,
+                }
+            ))
         );
-        assert_eq!(from_str_test(r#" "\"bar" "#), Ok((s(r#""bar"#), 9)));
-        assert_eq!(from_str_test(r#" "foo\"" "#), Ok((s(r#"foo""#), 9)));
-        assert_eq!(from_str_test(r#" "\"" "#), Ok((s(r#"""#), 6)));
 
-        // non-excaped " preceded by backslashes
+        let mut de = Deserializer::new(b"[1,2]", None);
+        <[u8; 2] as serde::Deserialize>::deserialize(&mut de).unwrap();
+        assert_eq!(de.stats(), None);
+
+        let mut de = Deserializer::new(b"[1,2]", None).with_stats();
+        <[u8; 2] as serde::Deserialize>::deserialize(&mut de).unwrap();
+        assert_eq!(de.stats().map(|stats| stats.scalars), Some(2));
+        de.reset(b"3");
+        assert_eq!(de.stats(), Some(Stats::default()));
+    }
+
+    #[test]
+    fn max_string_len() {
+        use crate::de::{Config, Error};
+
+        let config = Config::default().max_string_len(4);
         assert_eq!(
-            from_str_test(r#" "foo bar\\" "#),
-            Ok((s(r#"foo bar\"#), 13))
+            crate::from_str_with_config(r#""abcd""#, &config),
+            Ok(("abcd", 6))
         );
         assert_eq!(
-            from_str_test(r#" "foo bar\\\\" "#),
-            Ok((s(r#"foo bar\\"#), 15))
+            crate::from_str_with_config::<&str>(r#""abcde""#, &config),
+            Err(Error::StringTooLong)
         );
+        // Escape sequences count with their escaped length
         assert_eq!(
-            from_str_test(r#" "foo bar\\\\\\" "#),
-            Ok((s(r#"foo bar\\\"#), 17))
+            crate::from_str_with_config::<&str>(r#""a\nbc""#, &config),
+            Err(Error::StringTooLong)
         );
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Short {
+            a: u8,
+        }
         assert_eq!(
-            from_str_test(r#" "foo bar\\\\\\\\" "#),
-            Ok((s(r#"foo bar\\\\"#), 19))
+            crate::from_str_with_config::<Short>(r#"{"a":1,"longer":0}"#, &config),
+            Err(Error::StringTooLong)
+        );
+
+        let long = [b'x'; 1000];
+        let mut json = heapless::Vec::<u8, 1002>::new();
+        json.push(b'"').unwrap();
+        json.extend_from_slice(&long).unwrap();
+        json.push(b'"').unwrap();
+        assert!(crate::from_slice::<&str>(&json).is_ok());
+        assert_eq!(
+            crate::de::from_slice_with_config::<&str>(&json, &Config::default().max_string_len(16)),
+            Err(Error::StringTooLong)
         );
-        assert_eq!(from_str_test(r#" "\\" "#), Ok((s(r#"\"#), 6)));
     }
 
     #[test]
-    fn tuple_of_str() {
-        fn s(s: &'static str) -> heapless::String<1024> {
-            s.parse().expect("Failed to create test string")
-        }
-
-        fn from_str_test<'de, T: serde::Deserialize<'de>>(
-            s: &'de str,
-        ) -> super::Result<(T, usize)> {
-            crate::from_str_escaped(s, &mut [0; 16])
-        }
-
-        // The combined length of the first and third strings are longer than the buffer, but that's OK,
-        // as escaped strings are deserialized into owned str types, e.g. `heapless::String`.
-        // The second string is longer than the buffer, but that's OK, as strings which aren't escaped
-        // are deserialized as str's borrowed from the input
+    fn max_elements() {
+        use crate::de::{Config, Error};
 
+        let config = Config::default().max_elements(3);
         assert_eq!(
-            from_str_test(
-                r#" [ "AAAAAAAAAAAA\n", "BBBBBBBBBBBBBBBBBBBBBBBB", "CCCCCCCCCCCC\n" ] "#
-            ),
-            Ok((
-                (
-                    s("AAAAAAAAAAAA\n"),
-                    "BBBBBBBBBBBBBBBBBBBBBBBB",
-                    s("CCCCCCCCCCCC\n")
-                ),
-                68
-            ))
+            crate::from_str_with_config::<heapless::Vec<u8, 8>>("[1,2,3]", &config)
+                .map(|(v, _)| v.len()),
+            Ok(3)
         );
-    }
-
-    #[test]
-    fn escaped_str() {
         assert_eq!(
-            crate::from_str(r#""Hello\nWorld""#),
-            Ok((crate::str::EscapedStr(r#"Hello\nWorld"#), 14))
+            crate::from_str_with_config::<heapless::Vec<u8, 8>>("[1,2,3,4]", &config),
+            Err(Error::TooManyElements)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<[[u8; 3]; 2]>("[[1,2,3],[4,5,6]]", &config),
+            Ok(([[1, 2, 3], [4, 5, 6]], 17))
         );
-    }
 
-    #[test]
-    fn struct_bool() {
         #[derive(Debug, Deserialize, PartialEq)]
-        struct Led {
-            led: bool,
+        struct Point {
+            x: i8,
+            y: i8,
         }
-
         assert_eq!(
-            crate::from_str(r#"{ "led": true }"#),
-            Ok((Led { led: true }, 15))
+            crate::from_str_with_config(r#"{"x":1,"y":2,"z":3}"#, &config),
+            Ok((Point { x: 1, y: 2 }, 19))
         );
         assert_eq!(
-            crate::from_str(r#"{ "led": false }"#),
-            Ok((Led { led: false }, 16))
+            crate::from_str_with_config::<Point>(r#"{"x":1,"y":2,"z":3,"w":4}"#, &config),
+            Err(Error::TooManyElements)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<Point>(r#"{"x":1,"y":2,"z":[0,0,0,0]}"#, &config),
+            Err(Error::TooManyElements)
         );
     }
 
     #[test]
-    fn struct_i8() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        struct Temperature {
-            temperature: i8,
-        }
+    fn spanned() {
+        use crate::de::{from_str_spanned, Error};
 
+        let json = " \n [1, 2]\t ";
+        let (value, span) = from_str_spanned::<[u8; 2]>(json).unwrap();
+        assert_eq!(value, [1, 2]);
+        assert_eq!(span, 3..9);
+        assert_eq!(&json[span], "[1, 2]");
+
+        assert_eq!(from_str_spanned(r#""abc""#), Ok(("abc", 0..5)));
         assert_eq!(
-            crate::from_str(r#"{ "temperature": -17 }"#),
-            Ok((Temperature { temperature: -17 }, 22))
+            from_str_spanned::<u8>("  7 x"),
+            Err(Error::TrailingCharacters)
         );
-
         assert_eq!(
-            crate::from_str(r#"{ "temperature": -0 }"#),
-            Ok((Temperature { temperature: -0 }, 21))
+            from_str_spanned::<u8>("   "),
+            Err(Error::EofWhileParsingValue)
         );
+    }
+
+    #[test]
+    fn integer_keys() {
+        use crate::de::Error;
+        use heapless::FnvIndexMap;
+
+        let (map, _) = crate::from_str::<FnvIndexMap<u32, bool, 4>>(r#"{"5":true}"#).unwrap();
+        assert_eq!(map.get(&5), Some(&true));
+
+        let (map, _) =
+            crate::from_str::<FnvIndexMap<i8, &str, 4>>(r#"{ "-1" : "x", "1": "y" }"#).unwrap();
+        assert_eq!(map.get(&-1), Some(&"x"));
+        assert_eq!(map.get(&1), Some(&"y"));
+
+        let (map, _) = crate::from_str::<FnvIndexMap<bool, u8, 4>>(r#"{"false":0}"#).unwrap();
+        assert_eq!(map.get(&false), Some(&0));
 
+        for json in [r#"{"5x":true}"#, r#"{" 5":true}"#, r#"{"":true}"#] {
+            assert!(crate::from_str::<FnvIndexMap<u32, bool, 4>>(json).is_err());
+        }
         assert_eq!(
-            crate::from_str(r#"{ "temperature": 0 }"#),
-            Ok((Temperature { temperature: 0 }, 20))
+            crate::from_str::<FnvIndexMap<u8, bool, 4>>(r#"{"256":true}"#),
+            Err(Error::InvalidNumber)
+        );
+        assert_eq!(
+            crate::from_str::<FnvIndexMap<u8, bool, 4>>(r#"{"5 ":true}"#),
+            Err(Error::InvalidNumber)
         );
-
-        // out of range
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 128 }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": -129 }"#).is_err());
     }
 
     #[test]
-    fn struct_f32() {
+    fn field_alias() {
         #[derive(Debug, Deserialize, PartialEq)]
         struct Temperature {
-            temperature: f32,
+            #[serde(alias = "temp", alias = "t")]
+            temperature: i8,
         }
 
-        assert_eq!(
-            crate::from_str(r#"{ "temperature": -17.2 }"#),
-            Ok((Temperature { temperature: -17.2 }, 24))
-        );
+        for json in [r#"{"temperature":20}"#, r#"{"temp":20}"#, r#"{"t":20}"#] {
+            assert_eq!(
+                crate::from_str(json),
+                Ok((Temperature { temperature: 20 }, json.len()))
+            );
+        }
 
+        // Escaped keys only match once they're unescaped
+        let json = r#"{"te\u006dp":20}"#;
+        assert!(crate::from_str::<Temperature>(json).is_err());
         assert_eq!(
-            crate::from_str(r#"{ "temperature": -0.0 }"#),
-            Ok((Temperature { temperature: -0. }, 23))
+            crate::from_str_escaped(json, &mut [0; 8]),
+            Ok((Temperature { temperature: 20 }, json.len()))
         );
+    }
 
+    #[test]
+    fn quoted_non_finite() {
+        use crate::de::{Config, Error};
+
+        let config = Config::default().quoted_non_finite(true);
+        let (v, _) = crate::from_str_with_config::<f32>(r#""NaN""#, &config).unwrap();
+        assert!(v.is_nan());
         assert_eq!(
-            crate::from_str(r#"{ "temperature": -2.1e-3 }"#),
-            Ok((
-                Temperature {
-                    temperature: -2.1e-3
-                },
-                26
-            ))
+            crate::from_str_with_config(r#""Infinity""#, &config),
+            Ok((f32::INFINITY, 10))
         );
-
         assert_eq!(
-            crate::from_str(r#"{ "temperature": -3 }"#),
-            Ok((Temperature { temperature: -3. }, 21))
+            crate::from_str_with_config(r#"["-Infinity",1.5]"#, &config),
+            Ok(([f64::NEG_INFINITY, 1.5], 17))
         );
 
-        use core::f32;
+        // Only the non-finite spellings are accepted in quotes
+        for json in [r#""1.5""#, r#""nan""#, r#""inf""#, r#"" NaN""#] {
+            assert_eq!(
+                crate::from_str_with_config::<f64>(json, &config),
+                Err(Error::InvalidNumber)
+            );
+        }
 
         assert_eq!(
-            crate::from_str(r#"{ "temperature": -1e500 }"#),
-            Ok((
-                Temperature {
-                    temperature: f32::NEG_INFINITY
-                },
-                25
-            ))
+            crate::from_str::<f64>(r#""Infinity""#),
+            Err(Error::InvalidNumber)
         );
-
-        // NaNs will always compare unequal.
-        let (r, n): (Temperature, usize) = crate::from_str(r#"{ "temperature": null }"#).unwrap();
-        assert!(r.temperature.is_nan());
-        assert_eq!(n, 23);
-
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 1e1e1 }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": -2-2 }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 1 1 }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 0.0. }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": ä }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": None }"#).is_err());
     }
 
     #[test]
-    fn struct_option() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        struct Property<'a> {
-            #[serde(borrow)]
-            description: Option<&'a str>,
-        }
+    fn trim_nul() {
+        use crate::de::{from_slice_trim_nul, Error};
 
         assert_eq!(
-            crate::from_str(r#"{ "description": "An ambient temperature sensor" }"#),
-            Ok((
-                Property {
-                    description: Some("An ambient temperature sensor"),
-                },
-                50
-            ))
+            crate::from_slice::<bool>(b"true\0\0\0"),
+            Err(Error::TrailingCharacters)
         );
+        assert_eq!(from_slice_trim_nul(b"true\0\0\0"), Ok((true, 4)));
+        assert_eq!(from_slice_trim_nul(b" [1] \n\0"), Ok(([1u8], 6)));
+        assert_eq!(from_slice_trim_nul(b"true"), Ok((true, 4)));
 
+        // Only padding at the end is trimmed
         assert_eq!(
-            crate::from_str(r#"{ "description": null }"#),
-            Ok((Property { description: None }, 23))
+            from_slice_trim_nul::<bool>(b"true\0 \0"),
+            Err(Error::TrailingCharacters)
         );
-
         assert_eq!(
-            crate::from_str(r#"{}"#),
-            Ok((Property { description: None }, 2))
+            from_slice_trim_nul::<bool>(b"\0\0"),
+            Err(Error::EofWhileParsingValue)
         );
     }
 
     #[test]
-    fn struct_u8() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        struct Temperature {
-            temperature: u8,
+    fn raw_value() {
+        use crate::de::Error;
+        use crate::str::RawValue;
+        use serde_derive::Serialize;
+
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Envelope<'a> {
+            kind: &'a str,
+            #[serde(borrow)]
+            body: RawValue<'a>,
+            seq: u8,
         }
 
+        let json = r#"{"kind":"reading","body": {"t": [20, 21], "s":"a\"b"} ,"seq":3}"#;
+        let (envelope, _) = crate::from_str::<Envelope<'_>>(json).unwrap();
+        assert_eq!(envelope.body.get(), r#"{"t": [20, 21], "s":"a\"b"}"#);
+        assert_eq!(envelope.seq, 3);
+
+        // The fragment is written verbatim and the result parses again
+        let out = crate::to_string::<_, 128>(&envelope).unwrap();
         assert_eq!(
-            crate::from_str(r#"{ "temperature": 20 }"#),
-            Ok((Temperature { temperature: 20 }, 21))
+            out,
+            r#"{"kind":"reading","body":{"t": [20, 21], "s":"a\"b"},"seq":3}"#
         );
+        assert_eq!(crate::from_str(&out), Ok((envelope, out.len())));
 
+        for json in ["1.5e3", "null", r#""str""#, "[]"] {
+            assert_eq!(
+                crate::from_str::<RawValue<'_>>(json),
+                Ok((RawValue::new(json).unwrap(), json.len()))
+            );
+        }
         assert_eq!(
-            crate::from_str(r#"{ "temperature": 0 }"#),
-            Ok((Temperature { temperature: 0 }, 20))
+            crate::from_str::<RawValue<'_>>("[1,}"),
+            Err(Error::ExpectedSomeValue)
         );
 
-        // out of range
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 256 }"#).is_err());
-        assert!(crate::from_str::<Temperature>(r#"{ "temperature": -1 }"#).is_err());
-    }
-
-    #[test]
-    fn test_unit() {
-        assert_eq!(crate::from_str::<()>(r#"null"#), Ok(((), 4)));
-    }
-
-    #[test]
-    fn newtype_struct() {
-        #[derive(Deserialize, Debug, PartialEq)]
-        struct A(pub u32);
+        assert!(RawValue::new(" {} ").is_ok());
+        assert_eq!(RawValue::new("{} {}"), Err(Error::TrailingCharacters));
+        assert_eq!(RawValue::new(""), Err(Error::EofWhileParsingValue));
 
-        assert_eq!(crate::from_str::<A>(r#"54"#), Ok((A(54), 2)));
+        // Lenient options apply around raw values, but not inside them, so the output stays valid
+        let config = crate::de::Config::default()
+            .allow_comments(true)
+            .allow_trailing_commas(true);
+        assert_eq!(
+            crate::from_slice_with_config::<[RawValue<'_>; 1]>(b"[[1, /*c*/ 2,]]", &config),
+            Err(Error::ExpectedSomeValue)
+        );
+        let json = b"[ /* c */ [1, 2] , ]";
+        let ([raw], _) = crate::from_slice_with_config::<[RawValue<'_>; 1]>(json, &config).unwrap();
+        assert_eq!(raw.get(), "[1, 2]");
+        let mut buf = [0; 16];
+        let len = crate::to_slice(&[raw], &mut buf).unwrap();
+        assert_eq!(crate::de::validate(&buf[..len]), Ok(len));
     }
 
     #[test]
-    fn test_newtype_variant() {
-        #[derive(Deserialize, Debug, PartialEq)]
-        enum A {
-            A(u32),
-        }
-        let a = A::A(54);
-        let x = crate::from_str::<A>(r#"{"A":54}"#);
-        assert_eq!(x, Ok((a, 8)));
-    }
+    fn extras() {
+        use crate::str::RawValue;
+        use heapless::FnvIndexMap;
 
-    #[test]
-    fn test_struct_variant() {
-        #[derive(Deserialize, Debug, PartialEq)]
-        enum A {
-            A { x: u32, y: u16 },
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Inner {
+            b: u8,
         }
-        let a = A::A { x: 54, y: 720 };
-        let x = crate::from_str::<A>(r#"{"A": {"x":54,"y":720 } }"#);
-        assert_eq!(x, Ok((a, 25)));
-    }
 
-    #[test]
-    #[cfg(not(feature = "custom-error-messages"))]
-    fn struct_tuple() {
         #[derive(Debug, Deserialize, PartialEq)]
-        struct Xy(i8, i8);
-
-        assert_eq!(crate::from_str(r#"[10, 20]"#), Ok((Xy(10, 20), 8)));
-        assert_eq!(crate::from_str(r#"[10, -20]"#), Ok((Xy(10, -20), 9)));
+        struct Known<'a> {
+            a: u8,
+            inner: Inner,
+            #[serde(borrow)]
+            raw: RawValue<'a>,
+            list: [u8; 2],
+        }
 
-        // wrong number of args
+        let json = br#"{"a":1,"str": "x\"y","inner":{"obj":{"c":[1,{}]},"b":2},"raw":{"z":[3]},
+            "list":[4,5],"num" :-1.5e3 ,"new\u0020key":null}"#;
+        let mut extras = FnvIndexMap::<&str, RawValue<'_>, 8>::new();
+        let (known, _) = crate::de::from_slice_with_extras::<Known<'_>, _>(json, |key, raw| {
+            extras.insert(key, raw).unwrap();
+        })
+        .unwrap();
         assert_eq!(
-            crate::from_str::<Xy>(r#"[10]"#),
-            Err(crate::de::Error::CustomError)
-        );
-        assert_eq!(
-            crate::from_str::<Xy>(r#"[10, 20, 30]"#),
-            Err(crate::de::Error::TrailingCharacters)
+            known,
+            Known {
+                a: 1,
+                inner: Inner { b: 2 },
+                raw: RawValue::new(r#"{"z":[3]}"#).unwrap(),
+                list: [4, 5],
+            }
         );
-    }
-
-    #[test]
-    #[cfg(feature = "custom-error-messages")]
-    fn struct_tuple() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        struct Xy(i8, i8);
-
-        assert_eq!(crate::from_str(r#"[10, 20]"#), Ok((Xy(10, 20), 8)));
-        assert_eq!(crate::from_str(r#"[10, -20]"#), Ok((Xy(10, -20), 9)));
 
-        // wrong number of args
+        // Any kind of value at any depth, but not the values within skipped ones
+        let collected: heapless::Vec<(&str, &str), 8> =
+            extras.iter().map(|(key, raw)| (*key, raw.get())).collect();
         assert_eq!(
-            crate::from_str::<Xy>(r#"[10]"#),
-            Err(crate::de::Error::CustomErrorWithMessage(
-                "invalid length 1, expected tuple struct Xy with 2 elements"
-                    .parse()
-                    .unwrap()
-            ))
+            collected,
+            [
+                ("str", r#""x\"y""#),
+                ("obj", r#"{"c":[1,{}]}"#),
+                ("num", "-1.5e3"),
+                (r"new\u0020key", "null"),
+            ]
         );
+
+        // Values of maps and elements skipped with `tuple_prefix` aren't members skipped by a
+        // struct
+        let mut count = 0;
+        let config = crate::de::Config::default().tuple_prefix(true);
+        let mut collect = |_: &str, _: RawValue<'_>| count += 1;
+        let mut de = crate::de::Deserializer::new(br#"{"k":[1,2,3]}"#, None)
+            .with_config(config)
+            .with_extras(&mut collect);
+        let map: FnvIndexMap<&str, (u8,), 2> = serde::Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(map["k"], (1,));
+        assert_eq!(count, 0);
+
+        // Skipped members still have to be valid JSON
         assert_eq!(
-            crate::from_str::<Xy>(r#"[10, 20, 30]"#),
-            Err(crate::de::Error::TrailingCharacters)
+            crate::de::from_slice_with_extras::<Inner, _>(br#"{"b":1,"x":[1,}"#, |_, _| {}),
+            Err(crate::de::Error::ExpectedSomeValue)
         );
     }
 
+    // serde buffers the members of structs with flattened fields, which needs `alloc`
+    #[cfg(feature = "alloc")]
     #[test]
-    fn struct_with_array_field() {
+    fn flatten_extras() {
+        use heapless::FnvIndexMap;
+
         #[derive(Debug, Deserialize, PartialEq)]
-        struct Test {
-            status: bool,
-            point: [u32; 3],
+        struct Known<'a> {
+            a: u8,
+            #[serde(flatten, borrow)]
+            extra: FnvIndexMap<&'a str, i32, 4>,
         }
 
-        assert_eq!(
-            crate::from_str(r#"{ "status": true, "point": [1, 2, 3] }"#),
-            Ok((
-                Test {
-                    status: true,
-                    point: [1, 2, 3]
-                },
-                38
-            ))
-        );
+        let (known, _) = crate::from_str::<Known<'_>>(r#"{"a":1,"b":2}"#).unwrap();
+        assert_eq!(known.a, 1);
+        assert_eq!(known.extra.len(), 1);
+        assert_eq!(known.extra.get("b"), Some(&2));
+
+        let (known, _) = crate::from_str::<Known<'_>>(r#"{"c":-3,"a":1,"b":2}"#).unwrap();
+        assert_eq!(known.a, 1);
+        assert_eq!(known.extra.get("b"), Some(&2));
+        assert_eq!(known.extra.get("c"), Some(&-3));
+
+        let (known, _) = crate::from_str::<Known<'_>>(r#"{"a":1}"#).unwrap();
+        assert!(known.extra.is_empty());
     }
 
     #[test]
-    fn struct_with_tuple_field() {
+    fn enum_whitespace() {
         #[derive(Debug, Deserialize, PartialEq)]
-        struct Test {
-            status: bool,
-            point: (u32, u32, u32),
+        enum A {
+            Unit,
+            Newtype(u32),
+            Tuple(u8, u8),
+            Struct { x: u32, y: u16 },
+        }
+
+        for (json, value) in [
+            ("\t \"Unit\" \n", A::Unit),
+            (r#" { "Newtype" : 54 } "#, A::Newtype(54)),
+            ("{\n\t\"Newtype\"\n:\n54\n}", A::Newtype(54)),
+            (r#"{ "Tuple" : [ 1 , 2 ] }"#, A::Tuple(1, 2)),
+            (
+                r#"{"Struct": {"x":54,"y":720 } }"#,
+                A::Struct { x: 54, y: 720 },
+            ),
+            (
+                r#"{ "Struct" :{ "x" : 54 , "y" : 720 }}"#,
+                A::Struct { x: 54, y: 720 },
+            ),
+            (
+                "{\"Struct\"\n:\n{\n\"x\":54,\n\"y\":720\n}\n}\n",
+                A::Struct { x: 54, y: 720 },
+            ),
+        ] {
+            assert_eq!(crate::from_str(json), Ok((value, json.len())), "{}", json);
         }
 
         assert_eq!(
-            crate::from_str(r#"{ "status": true, "point": [1, 2, 3] }"#),
-            Ok((
-                Test {
-                    status: true,
-                    point: (1, 2, 3)
-                },
-                38
-            ))
+            crate::from_str::<[A; 2]>(r#"[ { "Newtype" : 1 } , "Unit" ]"#),
+            Ok(([A::Newtype(1), A::Unit], 30))
         );
     }
 
     #[test]
-    fn ignoring_extra_fields() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        struct Temperature {
-            temperature: u8,
-        }
+    fn tuple_prefix() {
+        use crate::de::{Config, Error};
 
+        let config = Config::default().tuple_prefix(true);
         assert_eq!(
-            crate::from_str(r#"{ "temperature": 20, "high": 80, "low": -10, "updated": true }"#),
-            Ok((Temperature { temperature: 20 }, 62))
+            crate::from_str_with_config("[1,2,3,4]", &config),
+            Ok(((1u8, 2u8), 9))
         );
-
         assert_eq!(
-            crate::from_str(
-                r#"{ "temperature": 20, "conditions": "windy", "forecast": "cloudy" }"#
-            ),
-            Ok((Temperature { temperature: 20 }, 66))
+            crate::from_str_with_config(r#"[1, "a", [{"x": null}], true ]"#, &config),
+            Ok(((1u8,), 30))
         );
-
         assert_eq!(
-            crate::from_str(r#"{ "temperature": 20, "hourly_conditions": ["windy", "rainy"] }"#),
-            Ok((Temperature { temperature: 20 }, 62))
+            crate::from_str_with_config("[1,2]", &config),
+            Ok(((1u8, 2u8), 5))
         );
-
         assert_eq!(
-            crate::from_str(
-                r#"{ "temperature": 20, "source": { "station": "dock", "sensors": ["front", "back"] } }"#
-            ),
-            Ok((Temperature { temperature: 20 }, 84))
+            crate::from_str_with_config("[[1,2,3],4]", &config),
+            Ok((([1u8, 2], 4u8), 11))
         );
 
+        // The skipped elements still have to be valid
+        assert!(crate::from_str_with_config::<(u8,)>("[1,2,}", &config).is_err());
+        assert!(crate::from_str_with_config::<(u8, u8)>("[1]", &config).is_err());
+
         assert_eq!(
-            crate::from_str(r#"{ "temperature": 20, "invalid": this-is-ignored }"#),
-            Ok((Temperature { temperature: 20 }, 49))
+            crate::from_str::<(u8, u8)>("[1,2,3,4]"),
+            Err(Error::TrailingCharacters)
         );
+    }
+
+    #[test]
+    fn result() {
+        type Response<'a> = core::result::Result<u32, &'a str>;
 
         assert_eq!(
-            crate::from_str::<Temperature>(r#"{ "temperature": 20, "broken": }"#),
-            Err(crate::de::Error::ExpectedSomeValue)
+            crate::from_str::<Response<'_>>(r#"{"Ok":42}"#),
+            Ok((Ok(42), 9))
         );
+        assert_eq!(
+            crate::from_str::<Response<'_>>(r#" { "Err" : "busy" } "#),
+            Ok((Err("busy"), 20))
+        );
+
+        for response in [Ok(7), Err("timeout")] {
+            let json = crate::to_string::<_, 32>(&response).unwrap();
+            assert_eq!(
+                crate::from_str::<Response<'_>>(&json).map(|(r, _)| r),
+                Ok(response)
+            );
+        }
+
+        assert!(crate::from_str::<Response<'_>>(r#"{"Ok":"7"}"#).is_err());
+        assert!(crate::from_str::<Response<'_>>(r#"{"Maybe":7}"#).is_err());
+        assert!(crate::from_str::<Response<'_>>(r#"{"Ok":7"#).is_err());
+    }
+
+    #[test]
+    fn char() {
+        fn from_str_test<'de, T: serde::Deserialize<'de>>(
+            s: &'de str,
+        ) -> super::Result<(T, usize)> {
+            crate::from_str_escaped(s, &mut [0; 8])
+        }
+
+        assert_eq!(from_str_test(r#""n""#), Ok(('n', 3)));
+        assert_eq!(from_str_test(r#""\"""#), Ok(('"', 4)));
+        assert_eq!(from_str_test(r#""\\""#), Ok(('\\', 4)));
+        assert_eq!(from_str_test(r#""/""#), Ok(('/', 3)));
+        assert_eq!(from_str_test(r#""\b""#), Ok(('\x08', 4)));
+        assert_eq!(from_str_test(r#""\f""#), Ok(('\x0C', 4)));
+        assert_eq!(from_str_test(r#""\n""#), Ok(('\n', 4)));
+        assert_eq!(from_str_test(r#""\r""#), Ok(('\r', 4)));
+        assert_eq!(from_str_test(r#""\t""#), Ok(('\t', 4)));
+        assert_eq!(from_str_test(r#""\u000b""#), Ok(('\x0B', 8)));
+        assert_eq!(from_str_test(r#""\u000B""#), Ok(('\x0B', 8)));
+        assert_eq!(from_str_test(r#""Σ""#), Ok(('Σ', 4)));
+    }
 
+    #[test]
+    fn char_from_code_point() {
+        use crate::de::{Config, Error};
+
+        let config = Config::default().char_from_code_point(true);
+        assert_eq!(crate::from_str_with_config(" 65", &config), Ok(('A', 3)));
         assert_eq!(
-            crate::from_str::<Temperature>(r#"{ "temperature": 20, "broken": [ }"#),
-            Err(crate::de::Error::ExpectedSomeValue)
+            crate::from_str_with_config("[128163,\"b\"]", &config),
+            Ok((['💣', 'b'], 12))
+        );
+        assert_eq!(
+            crate::from_str_with_config::<char>("55296", &config),
+            Err(Error::InvalidUnicodeCodePoint)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<char>("1114112", &config),
+            Err(Error::InvalidUnicodeCodePoint)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<char>("-1", &config),
+            Err(Error::InvalidType)
+        );
+
+        assert_eq!(crate::from_str::<char>("65"), Err(Error::InvalidType));
+    }
+
+    #[test]
+    fn str() {
+        // No escaping, so can borrow from the input
+        assert_eq!(crate::from_str(r#" "hello" "#), Ok(("hello", 9)));
+        assert_eq!(crate::from_str(r#" "" "#), Ok(("", 4)));
+        assert_eq!(crate::from_str(r#" " " "#), Ok((" ", 5)));
+        assert_eq!(crate::from_str(r#" "👏" "#), Ok(("👏", 8)));
+
+        fn s(s: &'static str) -> heapless::String<1024> {
+            s.parse().expect("Failed to create test string")
+        }
+
+        fn from_str_test<'de, T: serde::Deserialize<'de>>(
+            s: &'de str,
+        ) -> super::Result<(T, usize)> {
+            crate::from_str_escaped(s, &mut [0; 16])
+        }
+
+        // escaped " in the string content
+        assert_eq!(from_str_test(r#" "foo\"bar" "#), Ok((s(r#"foo"bar"#), 12)));
+        assert_eq!(
+            from_str_test(r#" "foo\\\"bar" "#),
+            Ok((s(r#"foo\"bar"#), 14))
+        );
+        assert_eq!(
+            from_str_test(r#" "foo\"\"bar" "#),
+            Ok((s(r#"foo""bar"#), 14))
         );
+        assert_eq!(from_str_test(r#" "\"bar" "#), Ok((s(r#""bar"#), 9)));
+        assert_eq!(from_str_test(r#" "foo\"" "#), Ok((s(r#"foo""#), 9)));
+        assert_eq!(from_str_test(r#" "\"" "#), Ok((s(r#"""#), 6)));
 
+        // non-excaped " preceded by backslashes
         assert_eq!(
-            crate::from_str::<Temperature>(r#"{ "temperature": 20, "broken": ] }"#),
-            Err(crate::de::Error::ExpectedSomeValue)
+            from_str_test(r#" "foo bar\\" "#),
+            Ok((s(r#"foo bar\"#), 13))
+        );
+        assert_eq!(
+            from_str_test(r#" "foo bar\\\\" "#),
+            Ok((s(r#"foo bar\\"#), 15))
+        );
+        assert_eq!(
+            from_str_test(r#" "foo bar\\\\\\" "#),
+            Ok((s(r#"foo bar\\\"#), 17))
+        );
+        assert_eq!(
+            from_str_test(r#" "foo bar\\\\\\\\" "#),
+            Ok((s(r#"foo bar\\\\"#), 19))
         );
+        assert_eq!(from_str_test(r#" "\\" "#), Ok((s(r#"\"#), 6)));
     }
 
     #[test]
-    #[cfg(feature = "custom-error-messages")]
-    fn preserve_short_error_message() {
-        use serde::de::Error;
+    fn tuple_of_str() {
+        fn s(s: &'static str) -> heapless::String<1024> {
+            s.parse().expect("Failed to create test string")
+        }
+
+        fn from_str_test<'de, T: serde::Deserialize<'de>>(
+            s: &'de str,
+        ) -> super::Result<(T, usize)> {
+            crate::from_str_escaped(s, &mut [0; 16])
+        }
+
+        // The combined length of the first and third strings are longer than the buffer, but that's OK,
+        // as escaped strings are deserialized into owned str types, e.g. `heapless::String`.
+        // The second string is longer than the buffer, but that's OK, as strings which aren't escaped
+        // are deserialized as str's borrowed from the input
+
         assert_eq!(
-            crate::de::Error::custom("something bad happened"),
-            crate::de::Error::CustomErrorWithMessage("something bad happened".parse().unwrap())
+            from_str_test(
+                r#" [ "AAAAAAAAAAAA\n", "BBBBBBBBBBBBBBBBBBBBBBBB", "CCCCCCCCCCCC\n" ] "#
+            ),
+            Ok((
+                (
+                    s("AAAAAAAAAAAA\n"),
+                    "BBBBBBBBBBBBBBBBBBBBBBBB",
+                    s("CCCCCCCCCCCC\n")
+                ),
+                68
+            ))
         );
     }
 
     #[test]
-    #[cfg(feature = "custom-error-messages")]
-    fn truncate_error_message() {
-        use serde::de::Error;
+    fn escaped_str() {
         assert_eq!(
-            crate::de::Error::custom("0123456789012345678901234567890123456789012345678901234567890123 <- after here the message should be truncated"),
-            crate::de::Error::CustomErrorWithMessage(
-                "0123456789012345678901234567890123456789012345678901234567890123".parse().unwrap()
-            )
+            crate::from_str(r#""Hello\nWorld""#),
+            Ok((crate::str::EscapedStr(r#"Hello\nWorld"#), 14))
         );
     }
 
-    // See https://iot.mozilla.org/wot/#thing-resource
     #[test]
-    fn wot() {
+    fn struct_bool() {
         #[derive(Debug, Deserialize, PartialEq)]
-        struct Thing<'a> {
-            #[serde(borrow)]
-            properties: Properties<'a>,
-            #[serde(rename = "type")]
-            ty: Type,
+        struct Led {
+            led: bool,
         }
 
+        assert_eq!(
+            crate::from_str(r#"{ "led": true }"#),
+            Ok((Led { led: true }, 15))
+        );
+        assert_eq!(
+            crate::from_str(r#"{ "led": false }"#),
+            Ok((Led { led: false }, 16))
+        );
+    }
+
+    #[test]
+    fn struct_i8() {
         #[derive(Debug, Deserialize, PartialEq)]
-        struct Properties<'a> {
-            #[serde(borrow)]
-            temperature: Property<'a>,
-            #[serde(borrow)]
-            humidity: Property<'a>,
-            #[serde(borrow)]
-            led: Property<'a>,
+        struct Temperature {
+            temperature: i8,
+        }
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -17 }"#),
+            Ok((Temperature { temperature: -17 }, 22))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -0 }"#),
+            Ok((Temperature { temperature: -0 }, 21))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": 0 }"#),
+            Ok((Temperature { temperature: 0 }, 20))
+        );
+
+        // out of range
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 128 }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": -129 }"#).is_err());
+    }
+
+    #[test]
+    fn struct_f32() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Temperature {
+            temperature: f32,
         }
 
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -17.2 }"#),
+            Ok((Temperature { temperature: -17.2 }, 24))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -0.0 }"#),
+            Ok((Temperature { temperature: -0. }, 23))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -2.1e-3 }"#),
+            Ok((
+                Temperature {
+                    temperature: -2.1e-3
+                },
+                26
+            ))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -3 }"#),
+            Ok((Temperature { temperature: -3. }, 21))
+        );
+
+        use core::f32;
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": -1e500 }"#),
+            Ok((
+                Temperature {
+                    temperature: f32::NEG_INFINITY
+                },
+                25
+            ))
+        );
+
+        // NaNs will always compare unequal.
+        let (r, n): (Temperature, usize) = crate::from_str(r#"{ "temperature": null }"#).unwrap();
+        assert!(r.temperature.is_nan());
+        assert_eq!(n, 23);
+
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 1e1e1 }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": -2-2 }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 1 1 }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 0.0. }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": ä }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": None }"#).is_err());
+    }
+
+    #[test]
+    fn negative_zero() {
+        // `-0.0 == 0.0`, so the sign bit has to be checked explicitly
+        let (value, _) = crate::from_str::<f32>("-0.0").unwrap();
+        assert!(value == 0.0 && value.is_sign_negative());
+        let (value, _) = crate::from_str::<f32>("-0").unwrap();
+        assert!(value == 0.0 && value.is_sign_negative());
+        let (value, _) = crate::from_str::<f64>("-0.0e3").unwrap();
+        assert!(value == 0.0 && value.is_sign_negative());
+
+        let (value, _) = crate::from_str::<f32>("0.0").unwrap();
+        assert!(value.is_sign_positive());
+    }
+
+    #[test]
+    fn struct_option() {
         #[derive(Debug, Deserialize, PartialEq)]
         struct Property<'a> {
-            #[serde(rename = "type")]
-            ty: Type,
-            unit: Option<&'a str>,
             #[serde(borrow)]
             description: Option<&'a str>,
-            href: &'a str,
         }
 
         assert_eq!(
-            crate::from_str::<Thing<'_>>(
-                r#"
-                    {
-                    "type": "thing",
+            crate::from_str(r#"{ "description": "An ambient temperature sensor" }"#),
+            Ok((
+                Property {
+                    description: Some("An ambient temperature sensor"),
+                },
+                50
+            ))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "description": null }"#),
+            Ok((Property { description: None }, 23))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{}"#),
+            Ok((Property { description: None }, 2))
+        );
+    }
+
+    #[test]
+    fn nested_option() {
+        // JSON has a single `null`, which always belongs to the outermost `Option`, like in
+        // serde_json
+        assert_eq!(
+            crate::from_str::<Option<Option<u32>>>("null"),
+            Ok((None, 4))
+        );
+        assert_eq!(
+            crate::from_str::<Option<Option<u32>>>(" 5 "),
+            Ok((Some(Some(5)), 3))
+        );
+        assert_eq!(
+            crate::from_str::<[Option<Option<u32>>; 2]>("[null,7]"),
+            Ok(([None, Some(Some(7))], 8))
+        );
+        assert_eq!(
+            crate::from_str::<Option<Option<u32>>>("nul"),
+            Err(crate::de::Error::ExpectedSomeIdent)
+        );
+
+        // The inner `None` can't be told apart when serialized
+        assert_eq!(
+            &*crate::to_string::<_, 8>(&Some(None::<u32>)).unwrap(),
+            "null"
+        );
+    }
+
+    #[test]
+    fn option_str_borrows() {
+        let json = r#""sensor""#;
+        let (value, _): (Option<&str>, _) = crate::from_str(json).unwrap();
+        assert_eq!(value.map(str::as_ptr), Some(json[1..].as_ptr()));
+
+        // Unescaped strings borrow from the input even if a scratch buffer is provided
+        let scratch = &mut [0xaa; 8];
+        let (value, _): (Option<&str>, _) = crate::from_str_escaped(json, scratch).unwrap();
+        assert_eq!(value.map(str::as_ptr), Some(json[1..].as_ptr()));
+        assert_eq!(scratch, &[0xaa; 8]);
+
+        assert_eq!(
+            crate::from_str_escaped("null", scratch),
+            Ok((None::<&str>, 4))
+        );
+        assert_eq!(scratch, &[0xaa; 8]);
+    }
+
+    #[test]
+    fn struct_default() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config {
+            rate: u32,
+            #[serde(default)]
+            verbose: bool,
+        }
+
+        assert_eq!(
+            crate::from_str(r#"{ "rate": 100 }"#),
+            Ok((
+                Config {
+                    rate: 100,
+                    verbose: false
+                },
+                15
+            ))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "rate": 100, "verbose": true }"#),
+            Ok((
+                Config {
+                    rate: 100,
+                    verbose: true
+                },
+                32
+            ))
+        );
+
+        // ignored fields before and after the missing one
+        assert_eq!(
+            crate::from_str(r#"{ "extra": [1, {"x": "y"}], "rate": 100, "more": null }"#),
+            Ok((
+                Config {
+                    rate: 100,
+                    verbose: false
+                },
+                55
+            ))
+        );
+
+        // fields without `#[serde(default)]` are still required
+        assert!(crate::from_str::<Config>(r#"{ "verbose": true }"#).is_err());
+    }
+
+    #[test]
+    fn struct_u8() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Temperature {
+            temperature: u8,
+        }
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": 20 }"#),
+            Ok((Temperature { temperature: 20 }, 21))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": 0 }"#),
+            Ok((Temperature { temperature: 0 }, 20))
+        );
+
+        // out of range
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": 256 }"#).is_err());
+        assert!(crate::from_str::<Temperature>(r#"{ "temperature": -1 }"#).is_err());
+    }
+
+    #[test]
+    fn test_unit() {
+        assert_eq!(crate::from_str::<()>(r#"null"#), Ok(((), 4)));
+    }
+
+    #[test]
+    fn newtype_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct A(pub u32);
+
+        assert_eq!(crate::from_str::<A>(r#"54"#), Ok((A(54), 2)));
+    }
+
+    #[test]
+    fn test_newtype_variant() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum A {
+            A(u32),
+        }
+        let a = A::A(54);
+        let x = crate::from_str::<A>(r#"{"A":54}"#);
+        assert_eq!(x, Ok((a, 8)));
+    }
+
+    #[test]
+    fn test_struct_variant() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum A {
+            A { x: u32, y: u16 },
+        }
+        let a = A::A { x: 54, y: 720 };
+        let x = crate::from_str::<A>(r#"{"A": {"x":54,"y":720 } }"#);
+        assert_eq!(x, Ok((a, 25)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "custom-error-messages"))]
+    fn struct_tuple() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Xy(i8, i8);
+
+        assert_eq!(crate::from_str(r#"[10, 20]"#), Ok((Xy(10, 20), 8)));
+        assert_eq!(crate::from_str(r#"[10, -20]"#), Ok((Xy(10, -20), 9)));
+
+        // wrong number of args
+        assert_eq!(
+            crate::from_str::<Xy>(r#"[10]"#),
+            Err(crate::de::Error::InvalidLength)
+        );
+        assert_eq!(
+            crate::from_str::<Xy>(r#"[10, 20, 30]"#),
+            Err(crate::de::Error::TrailingCharacters)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "custom-error-messages")]
+    fn struct_tuple() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Xy(i8, i8);
+
+        assert_eq!(crate::from_str(r#"[10, 20]"#), Ok((Xy(10, 20), 8)));
+        assert_eq!(crate::from_str(r#"[10, -20]"#), Ok((Xy(10, -20), 9)));
+
+        // wrong number of args
+        assert_eq!(
+            crate::from_str::<Xy>(r#"[10]"#),
+            Err(crate::de::Error::CustomErrorWithMessage(
+                "invalid length 1, expected tuple struct Xy with 2 elements"
+                    .parse()
+                    .unwrap()
+            ))
+        );
+        assert_eq!(
+            crate::from_str::<Xy>(r#"[10, 20, 30]"#),
+            Err(crate::de::Error::TrailingCharacters)
+        );
+    }
+
+    #[test]
+    fn struct_with_array_field() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            status: bool,
+            point: [u32; 3],
+        }
+
+        assert_eq!(
+            crate::from_str(r#"{ "status": true, "point": [1, 2, 3] }"#),
+            Ok((
+                Test {
+                    status: true,
+                    point: [1, 2, 3]
+                },
+                38
+            ))
+        );
+    }
+
+    #[test]
+    fn struct_with_tuple_field() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Test {
+            status: bool,
+            point: (u32, u32, u32),
+        }
+
+        assert_eq!(
+            crate::from_str(r#"{ "status": true, "point": [1, 2, 3] }"#),
+            Ok((
+                Test {
+                    status: true,
+                    point: (1, 2, 3)
+                },
+                38
+            ))
+        );
+    }
+
+    #[test]
+    fn ignoring_extra_fields() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Temperature {
+            temperature: u8,
+        }
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": 20, "high": 80, "low": -10, "updated": true }"#),
+            Ok((Temperature { temperature: 20 }, 62))
+        );
+
+        assert_eq!(
+            crate::from_str(
+                r#"{ "temperature": 20, "conditions": "windy", "forecast": "cloudy" }"#
+            ),
+            Ok((Temperature { temperature: 20 }, 66))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": 20, "hourly_conditions": ["windy", "rainy"] }"#),
+            Ok((Temperature { temperature: 20 }, 62))
+        );
+
+        assert_eq!(
+            crate::from_str(
+                r#"{ "temperature": 20, "source": { "station": "dock", "sensors": ["front", "back"] } }"#
+            ),
+            Ok((Temperature { temperature: 20 }, 84))
+        );
+
+        assert_eq!(
+            crate::from_str(r#"{ "temperature": 20, "unit": null, "scale": -1.5e-3 }"#),
+            Ok((Temperature { temperature: 20 }, 53))
+        );
+
+        // Ignored values still have to be valid JSON
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "invalid": this-is-ignored }"#),
+            Err(crate::de::Error::ExpectedSomeIdent)
+        );
+
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "invalid": ignored }"#),
+            Err(crate::de::Error::ExpectedSomeValue)
+        );
+
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "invalid": 1.e5 }"#),
+            Err(crate::de::Error::InvalidNumber)
+        );
+
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "invalid": "\x" }"#),
+            Err(crate::de::Error::InvalidEscapeSequence)
+        );
+
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "broken": }"#),
+            Err(crate::de::Error::ExpectedSomeValue)
+        );
+
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "broken": [ }"#),
+            Err(crate::de::Error::ExpectedSomeValue)
+        );
+
+        assert_eq!(
+            crate::from_str::<Temperature>(r#"{ "temperature": 20, "broken": ] }"#),
+            Err(crate::de::Error::ExpectedSomeValue)
+        );
+    }
+
+    #[test]
+    fn is_eof() {
+        use crate::de::Error;
+
+        for error in [
+            Error::EofWhileParsingList,
+            Error::EofWhileParsingObject,
+            Error::EofWhileParsingString,
+            Error::EofWhileParsingNumber,
+            Error::EofWhileParsingValue,
+        ] {
+            assert!(error.is_eof(), "{:?}", error);
+        }
+        for error in [
+            Error::ExpectedSomeValue,
+            Error::InvalidNumber,
+            Error::TrailingCharacters,
+            Error::EscapedStringIsTooLong,
+            Error::CustomError,
+        ] {
+            assert!(!error.is_eof(), "{:?}", error);
+        }
+
+        // Any truncated document fails with an EOF error
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Doc<'a> {
+            #[serde(borrow)]
+            a: (i32, &'a str, [u8; 0]),
+            b: Option<i64>,
+        }
+
+        let json = r#" {"a" : [-12,"x\"y\u00e4",[]], "b":-150} "#;
+        for len in 0..json.trim_end().len() {
+            let error = crate::from_str::<Doc<'_>>(&json[..len]).unwrap_err();
+            assert!(error.is_eof(), "{:?} for {}", error, &json[..len]);
+        }
+        assert!(crate::from_str::<Doc<'_>>(json).is_ok());
+    }
+
+    #[test]
+    fn escaped_prefix() {
+        use crate::str::EscapedStr;
+
+        let json = r#""This part is long and needs no unescaping at all, unlike \u2600\n""#;
+        let (escaped, _) = crate::from_str::<EscapedStr<'_>>(json).unwrap();
+
+        // The prefix is borrowed from the input
+        let prefix = escaped.borrow_prefix();
+        assert_eq!(prefix.len(), 57);
+        assert_eq!(prefix.as_ptr(), json[1..].as_ptr());
+
+        // Only the tail is unescaped
+        let mut tail = heapless::String::<4>::new();
+        EscapedStr(&escaped.0[prefix.len()..])
+            .write_unescaped(&mut tail)
+            .unwrap();
+        assert_eq!(tail, "\u{2600}\n");
+
+        assert_eq!(EscapedStr("no escapes").borrow_prefix(), "no escapes");
+        assert_eq!(EscapedStr(r"\n").borrow_prefix(), "");
+        assert_eq!(EscapedStr("").borrow_prefix(), "");
+    }
+
+    #[test]
+    fn unescape_errors() {
+        use crate::str::{EscapedStr, StringUnescapeError};
+
+        fn unescape(s: &str) -> core::result::Result<heapless::String<16>, StringUnescapeError> {
+            let mut string = heapless::String::new();
+            EscapedStr(s).write_unescaped(&mut string)?;
+            Ok(string)
+        }
+
+        assert_eq!(
+            unescape(r"a\x"),
+            Err(StringUnescapeError::UnknownEscape('x'))
+        );
+        assert_eq!(
+            unescape(r"a\"),
+            Err(StringUnescapeError::UnterminatedEscape)
+        );
+        assert_eq!(
+            unescape(r"\u12"),
+            Err(StringUnescapeError::IncompleteUnicode)
+        );
+        assert_eq!(
+            unescape(r"\uZZZZ"),
+            Err(StringUnescapeError::InvalidUnicodeDigits)
+        );
+        assert_eq!(
+            unescape(r"\u+123"),
+            Err(StringUnescapeError::InvalidUnicodeDigits)
+        );
+        assert_eq!(unescape(r"\uD83D"), Err(StringUnescapeError::LoneSurrogate));
+        assert_eq!(
+            unescape(r"\uD83Dx"),
+            Err(StringUnescapeError::LoneSurrogate)
+        );
+        assert_eq!(
+            unescape(r"\uD83D\u0041"),
+            Err(StringUnescapeError::LoneSurrogate)
+        );
+        assert_eq!(unescape(r"\uDCA3"), Err(StringUnescapeError::LoneSurrogate));
+
+        assert_eq!(unescape(r"\uD83D\uDCA3!").as_deref(), Ok("💣!"));
+        assert_eq!(unescape(r"\u00e4\u00C4").as_deref(), Ok("äÄ"));
+        assert_eq!(
+            unescape(r"0123456789abcdef\n"),
+            Err(StringUnescapeError::WriteFailed)
+        );
+
+        // The deserializer reports all of them as an invalid escape sequence
+        assert_eq!(
+            crate::from_str_escaped::<&str>(r#""\uD83D""#, &mut [0; 8]),
+            Err(crate::de::Error::InvalidEscapeSequence)
+        );
+        let (string, len) =
+            crate::from_str_escaped::<heapless::String<8>>(r#""\uD83D\uDCA3""#, &mut [0; 8])
+                .unwrap();
+        assert_eq!((&*string, len), ("💣", 14));
+    }
+
+    #[test]
+    fn error_position() {
+        use core::fmt::Write;
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize)]
+        struct Led {
+            _led: bool,
+        }
+
+        let mut de = crate::de::Deserializer::new(br#"{ "_led" :  "on" }"#, None);
+        let error = Led::deserialize(&mut de).unwrap_err();
+
+        let mut string = heapless::String::<32>::new();
+        write!(string, "{}", error.at(de.position())).unwrap();
+        assert_eq!(string, "InvalidType at byte 12");
+
+        string.clear();
+        write!(string, "{}", error).unwrap();
+        assert_eq!(string, "Invalid type");
+    }
+
+    #[test]
+    fn reset() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Led {
+            led: bool,
+        }
+
+        let mut scratch = [0; 8];
+        let mut de = crate::de::Deserializer::new(br#"{"led":true}"#, Some(&mut scratch))
+            .with_config(crate::de::Config::default().allow_trailing_commas(true));
+        assert_eq!(Led::deserialize(&mut de), Ok(Led { led: true }));
+        assert_eq!(de.end(), Ok(12));
+
+        de.reset(br#" { "led" : false, } "#);
+        assert_eq!(Led::deserialize(&mut de), Ok(Led { led: false }));
+        assert_eq!(de.end(), Ok(20));
+
+        de.reset(br#""\u0041\n""#);
+        let value: heapless::String<8> = Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(value, "A\n");
+        assert_eq!(de.end(), Ok(10));
+
+        // The field mask and extras apply to every document
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Partial {
+            a: u8,
+            b: Option<u8>,
+        }
+
+        let fields = ["a", "c"];
+        let mut extras = heapless::Vec::<&str, 4>::new();
+        let mut collect = |key, _| extras.push(key).unwrap();
+        let mut de = crate::de::Deserializer::new(br#"{"a":1,"b":2,"c":3}"#, None)
+            .with_field_mask(&fields)
+            .with_extras(&mut collect);
+        assert_eq!(Partial::deserialize(&mut de), Ok(Partial { a: 1, b: None }));
+        de.reset(br#"{"c":4,"b":5,"a":6}"#);
+        assert_eq!(Partial::deserialize(&mut de), Ok(Partial { a: 6, b: None }));
+        assert_eq!(extras, ["c", "c"]);
+    }
+
+    #[test]
+    fn ignored_any() {
+        use serde::de::IgnoredAny;
+
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(
+                r#" { "a": [1, -2.5, 3e+4, true, false, null, "x\"y\u00e4"], "b": { "c": {} , "d": [[]]} } "#
+            )
+            .map(|(_, len)| len),
+            Ok(88)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>("0").map(|(_, len)| len),
+            Ok(1)
+        );
+
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"{"a":}"#),
+            Err(crate::de::Error::ExpectedSomeValue)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"{"a":1"#),
+            Err(crate::de::Error::EofWhileParsingObject)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"[1,2"#),
+            Err(crate::de::Error::EofWhileParsingList)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"[1,]"#),
+            Err(crate::de::Error::TrailingComma)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"nul"#),
+            Err(crate::de::Error::ExpectedSomeIdent)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"-"#),
+            Err(crate::de::Error::EofWhileParsingNumber)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#"01"#),
+            Err(crate::de::Error::TrailingCharacters)
+        );
+        assert_eq!(
+            crate::from_str::<IgnoredAny>(r#""\u12""#),
+            Err(crate::de::Error::InvalidEscapeSequence)
+        );
+    }
+
+    #[test]
+    fn value_only() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Led {
+            led: bool,
+        }
+
+        assert_eq!(crate::from_str_value(" true "), Ok(true));
+        assert_eq!(
+            crate::from_str_value(r#"{ "led": false }"#),
+            Ok(Led { led: false })
+        );
+        assert_eq!(crate::from_slice_value(b"[0, 1, 2]"), Ok([0, 1, 2]));
+
+        // trailing characters are still rejected
+        assert_eq!(
+            crate::from_str_value::<bool>("true false"),
+            Err(crate::de::Error::TrailingCharacters)
+        );
+        assert_eq!(
+            crate::from_slice_value::<Led>(br#"{ "led": true } }"#),
+            Err(crate::de::Error::TrailingCharacters)
+        );
+    }
+
+    #[test]
+    fn max_depth() {
+        use crate::de::{validate, Config, Error, VALIDATE_MAX_DEPTH};
+        use serde::de::IgnoredAny;
+
+        /// `N` opening brackets followed by `N` closing ones
+        const fn nested<const N: usize, const L: usize>(open: u8, close: u8) -> [u8; L] {
+            let mut json = [close; L];
+            let mut i = 0;
+            while i < N {
+                json[i] = open;
+                i += 1;
+            }
+            json
+        }
+
+        // Far deeper than the stack allows without the limit
+        static DEEP: [u8; 2_000_000] = nested::<1_000_000, 2_000_000>(b'[', b']');
+        assert_eq!(validate(&DEEP), Err(Error::RecursionLimitExceeded));
+
+        let at_limit = nested::<VALIDATE_MAX_DEPTH, { 2 * VALIDATE_MAX_DEPTH }>(b'[', b']');
+        assert_eq!(validate(&at_limit), Ok(2 * VALIDATE_MAX_DEPTH));
+        let over_limit =
+            nested::<{ VALIDATE_MAX_DEPTH + 1 }, { 2 * VALIDATE_MAX_DEPTH + 2 }>(b'[', b']');
+        assert_eq!(validate(&over_limit), Err(Error::RecursionLimitExceeded));
+
+        // Objects, enums and skipped values count too, and siblings don't add up
+        let config = Config::default().max_depth(2);
+        let parse = |json: &str| {
+            crate::from_str_with_config::<IgnoredAny>(json, &config).map(|(_, len)| len)
+        };
+        assert_eq!(parse(r#"[[1],{"a":2},[]]"#), Ok(16));
+        assert_eq!(parse(r#"{"a":[[]]}"#), Err(Error::RecursionLimitExceeded));
+        assert_eq!(
+            crate::from_str_with_config::<[[[u8; 1]; 1]; 1]>("[[[1]]]", &config),
+            Err(Error::RecursionLimitExceeded)
+        );
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum E {
+            V([u8; 1]),
+        }
+        assert_eq!(
+            crate::from_str_with_config::<[E; 1]>(r#"[{"V":[1]}]"#, &config),
+            Err(Error::RecursionLimitExceeded)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<(E, E)>(
+                r#"[{"V":[1]},{"V":[2]}]"#,
+                &Config::default().max_depth(3)
+            ),
+            Ok(((E::V([1]), E::V([2])), 21))
+        );
+
+        // Unlimited by default
+        assert_eq!(
+            crate::from_str::<[[[u8; 0]; 1]; 1]>("[[[]]]"),
+            Ok(([[[]]], 6))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        use crate::de::{validate, Error};
+
+        assert_eq!(validate(b"true"), Ok(4));
+        assert_eq!(validate(b" [] "), Ok(4));
+        assert_eq!(validate(br#"{"a":[1,{"b":null}],"c":"\u00e4"}"#), Ok(33));
+        assert_eq!(validate(br#""unicode \u2600 ok""#), Ok(19));
+
+        // unbalanced brackets
+        assert_eq!(validate(b"[[1]"), Err(Error::EofWhileParsingList));
+        assert_eq!(validate(b"[1]]"), Err(Error::TrailingCharacters));
+        assert_eq!(validate(br#"{"a":{}"#), Err(Error::EofWhileParsingObject));
+        assert_eq!(validate(b"[1}"), Err(Error::ExpectedListCommaOrEnd));
+
+        // bad escapes
+        assert_eq!(validate(br#""\q""#), Err(Error::InvalidEscapeSequence));
+        assert_eq!(
+            validate(br#"["\u00G0"]"#),
+            Err(Error::InvalidEscapeSequence)
+        );
+
+        // trailing garbage
+        assert_eq!(validate(b"1 2"), Err(Error::TrailingCharacters));
+        assert_eq!(validate(b"{} x"), Err(Error::TrailingCharacters));
+
+        // missing or malformed values
+        assert_eq!(validate(b""), Err(Error::EofWhileParsingValue));
+        assert_eq!(validate(br#"{"a" 1}"#), Err(Error::ExpectedColon));
+        assert_eq!(validate(br#"{1:2}"#), Err(Error::KeyMustBeAString));
+        assert_eq!(validate(b"+1"), Err(Error::ExpectedSomeValue));
+    }
+
+    #[test]
+    #[cfg(feature = "custom-error-messages")]
+    fn preserve_short_error_message() {
+        use serde::de::Error;
+        assert_eq!(
+            crate::de::Error::custom("something bad happened"),
+            crate::de::Error::CustomErrorWithMessage("something bad happened".parse().unwrap())
+        );
+    }
+
+    /// Returns repeated digits, a little longer than a custom error message can hold
+    #[cfg(feature = "custom-error-messages")]
+    fn digits() -> heapless::String<{ crate::de::CUSTOM_ERROR_MESSAGE_LEN + 10 }> {
+        let mut digits = heapless::String::new();
+        while digits.len() <= crate::de::CUSTOM_ERROR_MESSAGE_LEN {
+            digits.push_str("0123456789").unwrap();
+        }
+        digits
+    }
+
+    #[test]
+    #[cfg(feature = "custom-error-messages")]
+    fn truncate_error_message() {
+        use crate::de::CUSTOM_ERROR_MESSAGE_LEN;
+        use serde::de::Error;
+
+        let digits = digits();
+        assert_eq!(
+            crate::de::Error::custom(format_args!(
+                "{} <- after here the message should be truncated",
+                &digits[..CUSTOM_ERROR_MESSAGE_LEN]
+            )),
+            crate::de::Error::CustomErrorWithMessage(
+                digits[..CUSTOM_ERROR_MESSAGE_LEN].parse().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "custom-error-messages")]
+    fn truncate_composite_error_message() {
+        use crate::de::CUSTOM_ERROR_MESSAGE_LEN;
+        use serde::de::Error;
+
+        // `fmt::Arguments` ignores the precision, so this used to overflow the message buffer
+        let digits = digits();
+        assert_eq!(
+            crate::de::Error::custom(format_args!("{}{}", digits, digits)),
+            crate::de::Error::CustomErrorWithMessage(
+                digits[..CUSTOM_ERROR_MESSAGE_LEN].parse().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "custom-error-messages-128")]
+    fn preserve_long_error_message() {
+        use serde::de::Error;
+
+        let message = "a custom error message which is quite a bit longer than the default \
+                       capacity of sixty-four bytes";
+        assert!(message.len() > 64);
+        assert_eq!(
+            crate::de::Error::custom(message),
+            crate::de::Error::CustomErrorWithMessage(message.parse().unwrap())
+        );
+    }
+
+    // See https://iot.mozilla.org/wot/#thing-resource
+    #[test]
+    fn wot() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Thing<'a> {
+            #[serde(borrow)]
+            properties: Properties<'a>,
+            #[serde(rename = "type")]
+            ty: Type,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Properties<'a> {
+            #[serde(borrow)]
+            temperature: Property<'a>,
+            #[serde(borrow)]
+            humidity: Property<'a>,
+            #[serde(borrow)]
+            led: Property<'a>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Property<'a> {
+            #[serde(rename = "type")]
+            ty: Type,
+            unit: Option<&'a str>,
+            #[serde(borrow)]
+            description: Option<&'a str>,
+            href: &'a str,
+        }
+
+        assert_eq!(
+            crate::from_str::<Thing<'_>>(
+                r#"
+                    {
+                    "type": "thing",
                     "properties": {
                         "temperature": {
                         "type": "number",
@@ -1459,31 +3518,686 @@ mod tests {
                     "#
             ),
             Ok((
-                Thing {
-                    properties: Properties {
-                        temperature: Property {
-                            ty: Type::Number,
-                            unit: Some("celsius"),
-                            description: Some("An ambient temperature sensor"),
-                            href: "/properties/temperature",
-                        },
-                        humidity: Property {
-                            ty: Type::Number,
-                            unit: Some("percent"),
-                            description: None,
-                            href: "/properties/humidity",
-                        },
-                        led: Property {
-                            ty: Type::Boolean,
-                            unit: None,
-                            description: Some("A red LED"),
-                            href: "/properties/led",
-                        },
-                    },
-                    ty: Type::Thing,
+                Thing {
+                    properties: Properties {
+                        temperature: Property {
+                            ty: Type::Number,
+                            unit: Some("celsius"),
+                            description: Some("An ambient temperature sensor"),
+                            href: "/properties/temperature",
+                        },
+                        humidity: Property {
+                            ty: Type::Number,
+                            unit: Some("percent"),
+                            description: None,
+                            href: "/properties/humidity",
+                        },
+                        led: Property {
+                            ty: Type::Boolean,
+                            unit: None,
+                            description: Some("A red LED"),
+                            href: "/properties/led",
+                        },
+                    },
+                    ty: Type::Thing,
+                },
+                852
+            ))
+        )
+    }
+
+    #[test]
+    fn auto() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Message<'a> {
+            plain: &'a str,
+            quoted: &'a str,
+            #[serde(borrow)]
+            list: [&'a str; 2],
+        }
+
+        let json = r#"{"plain":"abc","quoted":"\"x\"","list":["d\u00e9f","ghi"]}"#;
+        let mut scratch = [0; 16];
+        let scratch_range = scratch.as_ptr_range();
+        let (message, len) = crate::de::from_str_auto::<Message<'_>>(json, &mut scratch).unwrap();
+        assert_eq!(len, json.len());
+        assert_eq!(
+            message,
+            Message {
+                plain: "abc",
+                quoted: "\"x\"",
+                list: ["d\u{e9}f", "ghi"],
+            }
+        );
+
+        // Strings without escape sequences are borrowed from the input, the others from `scratch`
+        let input_range = json.as_bytes().as_ptr_range();
+        assert!(input_range.contains(&message.plain.as_ptr()));
+        assert!(input_range.contains(&message.list[1].as_ptr()));
+        assert!(scratch_range.contains(&message.quoted.as_ptr()));
+        assert!(scratch_range.contains(&message.list[0].as_ptr()));
+
+        // Unescaped strings don't overwrite each other
+        let (strings, _) =
+            crate::de::from_str_auto::<[&str; 3]>(r#"["\n","\t","\\"]"#, &mut scratch).unwrap();
+        assert_eq!(strings, ["\n", "\t", "\\"]);
+
+        let mut scratch = [0; 4];
+        assert_eq!(
+            crate::de::from_str_auto::<[&str; 2]>(r#"["\"ab","\"cd"]"#, &mut scratch),
+            Err(crate::de::Error::EscapedStringIsTooLong)
+        );
+    }
+
+    #[test]
+    fn fields() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(deny_unknown_fields)]
+        struct Partial {
+            a: u8,
+            b: Option<u8>,
+        }
+
+        // `b` is the wrong type and `c` is unknown, but neither reaches `Partial`
+        assert_eq!(
+            crate::de::from_slice_fields(br#"{"b":"x","a":1,"c":[{"a":2}]}"#, &["a"]),
+            Ok((Partial { a: 1, b: None }, 29))
+        );
+        assert_eq!(
+            crate::de::from_slice_fields(br#"{"a":1,"b":2}"#, &["a", "b"]),
+            Ok((Partial { a: 1, b: Some(2) }, 13))
+        );
+        assert!(crate::de::from_slice_fields::<Partial>(br#"{"b":2}"#, &["a"]).is_err());
+
+        // The selection can be built at runtime
+        let mut selected = heapless::String::<4>::new();
+        selected.push('b').unwrap();
+        let fields = ["a", selected.as_str()];
+        assert_eq!(
+            crate::de::from_slice_fields(br#"{"a":1,"b":2}"#, &fields),
+            Ok((Partial { a: 1, b: Some(2) }, 13))
+        );
+
+        // Skipped members still have to be well-formed
+        assert_eq!(
+            crate::de::from_slice_fields::<Partial>(br#"{"a":1,"c":[}"#, &["a"]),
+            Err(crate::de::Error::ExpectedSomeValue)
+        );
+        assert_eq!(
+            crate::de::from_slice_fields::<Partial>(br#"{"a":1 "c":2}"#, &["a"]),
+            Err(crate::de::Error::ExpectedObjectCommaOrEnd)
+        );
+
+        // Only the outermost object is masked
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Outer {
+            a: Partial,
+        }
+
+        assert_eq!(
+            crate::de::from_slice_fields(br#"{"a":{"a":1,"b":2},"b":{}}"#, &["a"]),
+            Ok((
+                Outer {
+                    a: Partial { a: 1, b: Some(2) }
+                },
+                26
+            ))
+        );
+        assert_eq!(
+            crate::de::from_slice_fields(br#"[{"a":1,"b":2}]"#, &["a"]),
+            Ok(([Partial { a: 1, b: Some(2) }], 15))
+        );
+    }
+
+    #[test]
+    fn nested_options() {
+        type Options<T> = Option<Option<Option<Option<Option<T>>>>>;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Wrap<T>(T);
+
+        assert_eq!(
+            crate::from_str::<Options<Options<u32>>>(" 5 "),
+            Ok((
+                Some(Some(Some(Some(Some(Some(Some(Some(Some(Some(5)))))))))),
+                3
+            ))
+        );
+        assert_eq!(
+            crate::from_str::<Options<Options<u32>>>("null"),
+            Ok((None, 4))
+        );
+        assert_eq!(
+            crate::from_str::<Options<Options<u32>>>("true"),
+            Err(crate::de::Error::InvalidType)
+        );
+
+        // Newtype structs recurse the same way, also mixed with options
+        assert_eq!(
+            crate::from_str::<Wrap<Wrap<Wrap<Wrap<Wrap<Options<Wrap<u8>>>>>>>>("[7]"),
+            Err(crate::de::Error::InvalidType)
+        );
+        assert_eq!(
+            crate::from_str::<Wrap<Wrap<Wrap<Wrap<Wrap<Options<Wrap<u8>>>>>>>>("7"),
+            Ok((
+                Wrap(Wrap(Wrap(Wrap(Wrap(Some(Some(Some(Some(Some(Wrap(
+                    7
+                ))))))))))),
+                1
+            ))
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        use core::fmt::Write;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Property<'a> {
+            #[serde(rename = "type")]
+            ty: Type,
+            unit: Option<&'a str>,
+            description: Option<&'a str>,
+            href: &'a str,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Thing<'a> {
+            #[serde(borrow)]
+            led: Property<'a>,
+            #[serde(rename = "type")]
+            ty: Type,
+        }
+
+        let members = [
+            r#""type": "number""#,
+            r#""unit": "celsius""#,
+            r#""description": "A sensor""#,
+            r#""href": "/properties/temperature""#,
+        ];
+        let expected = || Property {
+            ty: Type::Number,
+            unit: Some("celsius"),
+            description: Some("A sensor"),
+            href: "/properties/temperature",
+        };
+
+        // Every order of the members gives the same result
+        for order in 0..4 * 4 * 4 * 4 {
+            let indices = [order % 4, order / 4 % 4, order / 16 % 4, order / 64];
+            if (1..4).any(|i| indices[..i].contains(&indices[i])) {
+                continue;
+            }
+
+            let mut json = heapless::String::<128>::new();
+            json.push('{').unwrap();
+            for (i, &index) in indices.iter().enumerate() {
+                if i > 0 {
+                    json.push(',').unwrap();
+                }
+                json.push_str(members[index]).unwrap();
+            }
+            json.push('}').unwrap();
+
+            assert_eq!(
+                crate::from_str::<Property<'_>>(&json),
+                Ok((expected(), json.len())),
+                "{}",
+                json
+            );
+        }
+
+        // Optional fields before required ones, and missing optional fields
+        assert_eq!(
+            crate::from_str(r#"{"description":null,"unit":"percent","href":"/h","type":"number"}"#),
+            Ok((
+                Property {
+                    ty: Type::Number,
+                    unit: Some("percent"),
+                    description: None,
+                    href: "/h",
                 },
-                852
+                65
             ))
-        )
+        );
+        assert_eq!(
+            crate::from_str(r#"{"href":"/led","type":"boolean"}"#),
+            Ok((
+                Property {
+                    ty: Type::Boolean,
+                    unit: None,
+                    description: None,
+                    href: "/led",
+                },
+                32
+            ))
+        );
+
+        // Nested objects in either order
+        let led = r#"{"href":"/led","type":"boolean"}"#;
+        let expected = Thing {
+            led: Property {
+                ty: Type::Boolean,
+                unit: None,
+                description: None,
+                href: "/led",
+            },
+            ty: Type::Thing,
+        };
+        let mut json = heapless::String::<128>::new();
+        write!(json, r#"{{"type":"thing","led":{}}}"#, led).unwrap();
+        assert_eq!(
+            crate::from_str::<Thing<'_>>(&json).map(|(t, _)| t),
+            Ok(expected)
+        );
+        json.clear();
+        write!(json, r#"{{"led":{},"type":"thing"}}"#, led).unwrap();
+        assert_eq!(
+            crate::from_str::<Thing<'_>>(&json).map(|(t, _)| t.led.href),
+            Ok("/led")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "arrayvec")]
+    fn arrayvec() {
+        use arrayvec::{ArrayString, ArrayVec};
+
+        let (vec, _) = crate::from_str::<ArrayVec<u8, 4>>("[1, 2, 3]").unwrap();
+        assert_eq!(&vec[..], [1, 2, 3]);
+        assert_eq!(
+            crate::from_str::<ArrayString<8>>(r#""hello""#),
+            Ok((ArrayString::from("hello").unwrap(), 7))
+        );
+
+        // Over capacity
+        let too_long = crate::from_str::<ArrayVec<u8, 2>>("[1, 2, 3]");
+        #[cfg(not(feature = "custom-error-messages"))]
+        assert_eq!(too_long, Err(crate::de::Error::InvalidLength));
+        assert!(too_long.is_err());
+        let too_long = crate::from_str::<ArrayString<4>>(r#""hello""#);
+        #[cfg(not(feature = "custom-error-messages"))]
+        assert_eq!(too_long, Err(crate::de::Error::InvalidLength));
+        assert!(too_long.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn any() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Any<'a> {
+            Null,
+            Bool(bool),
+            Unsigned(u64),
+            Signed(i64),
+            Float(f64),
+            Str(&'a str),
+        }
+
+        assert_eq!(crate::from_str("null"), Ok((Any::Null, 4)));
+        assert_eq!(crate::from_str(" true"), Ok((Any::Bool(true), 5)));
+        assert_eq!(crate::from_str("42"), Ok((Any::Unsigned(42), 2)));
+        assert_eq!(crate::from_str("-42"), Ok((Any::Signed(-42), 3)));
+        assert_eq!(crate::from_str("4.5e1"), Ok((Any::Float(45.0), 5)));
+        assert_eq!(
+            crate::from_str("18446744073709551616"),
+            Ok((Any::Float(18446744073709551616.0), 20))
+        );
+        assert_eq!(crate::from_str(r#""foo""#), Ok((Any::Str("foo"), 5)));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn adjacently_tagged_enum() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "t", content = "c")]
+        enum Message {
+            Ping,
+            Value(u32),
+            Point { x: i8, y: i8 },
+        }
+
+        assert_eq!(crate::from_str(r#"{"t":"Ping"}"#), Ok((Message::Ping, 12)));
+        assert_eq!(
+            crate::from_str(r#"{"t":"Value","c":7}"#),
+            Ok((Message::Value(7), 19))
+        );
+        assert_eq!(
+            crate::from_str(r#"{ "t" : "Point", "c" : { "x" : 1, "y" : -2 } }"#),
+            Ok((Message::Point { x: 1, y: -2 }, 46))
+        );
+
+        // The content may come before the tag, in which case it is buffered
+        assert_eq!(
+            crate::from_str(r#"{"c":{"x":1,"y":-2},"t":"Point"}"#),
+            Ok((Message::Point { x: 1, y: -2 }, 32))
+        );
+        assert_eq!(
+            crate::from_str::<Message>(r#"{"t":"Nope","c":1}"#).map_err(|_| ()),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn lenient_whitespace() {
+        use crate::de::{Config, Error};
+
+        let json = "\x0C[1,\x0B2 ,\x0C 3]\x0C";
+        let config = Config::default().lenient_whitespace(true);
+        assert_eq!(
+            crate::from_str_with_config(json, &config),
+            Ok(([1u8, 2, 3], json.len()))
+        );
+
+        assert_eq!(crate::from_str::<[u8; 3]>(json), Err(Error::InvalidType));
+        assert_eq!(
+            crate::from_str::<[u8; 3]>("[1,2,3]\x0C"),
+            Err(Error::TrailingCharacters)
+        );
+    }
+
+    #[test]
+    fn config() {
+        use crate::de::{Config, Error};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Led {
+            on: bool,
+            brightness: [u8; 2],
+        }
+
+        let json = r#"{
+            // Whether the LED is lit
+            "on": true,
+            "brightness": [1, 2,], /* min, max */
+        }"#;
+        let led = Led {
+            on: true,
+            brightness: [1, 2],
+        };
+
+        let config = Config::default()
+            .allow_comments(true)
+            .allow_trailing_commas(true);
+        assert_eq!(
+            crate::from_str_with_config(json, &config),
+            Ok((led, json.len()))
+        );
+        assert_eq!(
+            crate::from_str_with_config::<[u8; 0]>("[ /* empty */ ]", &config),
+            Ok(([], 15))
+        );
+        assert_eq!(
+            crate::from_str_with_config::<&str>("\"//\" // comment", &config),
+            Ok(("//", 15))
+        );
+        assert_eq!(
+            crate::from_str_with_config::<heapless::Vec<u8, 4>>("[1,]", &config),
+            Ok((heapless::Vec::from_slice(&[1]).unwrap(), 4))
+        );
+
+        // A leading comma is still rejected
+        assert_eq!(
+            crate::from_str_with_config::<heapless::Vec<u8, 4>>("[,1]", &config),
+            Err(Error::InvalidType)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<heapless::Vec<u8, 4>>("[1,,]", &config),
+            Err(Error::InvalidType)
+        );
+
+        // Each option only enables its own extension
+        assert_eq!(
+            crate::from_str_with_config::<Led>(json, &Config::default().allow_comments(true)),
+            Err(Error::TrailingComma)
+        );
+        assert_eq!(
+            crate::from_str_with_config::<Led>(
+                json,
+                &Config::default().allow_trailing_commas(true)
+            ),
+            Err(Error::KeyMustBeAString)
+        );
+        assert_eq!(crate::from_str::<Led>(json), Err(Error::KeyMustBeAString));
+    }
+
+    #[test]
+    fn chunks() {
+        use crate::de::Error;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Reading<'a> {
+            sensor: &'a str,
+            value: i32,
+        }
+
+        let reading = Reading {
+            sensor: "temperature",
+            value: -5,
+        };
+        let scratch = &mut [0; 64];
+
+        // The string token straddles both chunks
+        let chunks: [&[u8]; 2] = [br#"{"sensor":"temper"#, br#"ature","value":-5}"#];
+        assert_eq!(crate::de::from_chunks(&chunks, scratch), Ok((reading, 35)));
+
+        // A single chunk is parsed in place
+        let chunk = br#"{"sensor":"humidity","value":40}"#;
+        let (reading, _): (Reading<'_>, _) =
+            crate::de::from_chunks(&[b"", chunk, b""], scratch).unwrap();
+        assert_eq!(reading.sensor.as_ptr(), chunk[11..].as_ptr());
+
+        assert_eq!(
+            crate::de::from_chunks::<Reading<'_>>(&chunks, &mut [0; 34]),
+            Err(Error::ChunksTooLong)
+        );
+        assert_eq!(
+            crate::de::from_chunks::<u8>(&[], &mut []),
+            Err(Error::EofWhileParsingValue)
+        );
+    }
+
+    #[test]
+    fn trailing() {
+        use crate::de::{trailing, Trailing};
+
+        let buf = b"42";
+        let (_, consumed) = crate::from_slice::<u32>(buf).unwrap();
+        assert_eq!(trailing(buf, consumed), Trailing::Nothing);
+
+        assert_eq!(trailing(b"42 \r\n", 2), Trailing::Whitespace);
+        assert_eq!(trailing(b"42 {", 2), Trailing::Data);
+
+        // Out of range `consumed` counts as nothing left
+        assert_eq!(trailing(b"42", 3), Trailing::Nothing);
+    }
+
+    #[test]
+    fn nul() {
+        let value = "a\0b\0";
+        let json = crate::to_string::<_, 32>(value).unwrap();
+        assert_eq!(json, r#""a\u0000b\u0000""#);
+
+        // The escaped path decodes NUL like any other character and doesn't stop at it
+        let (unescaped, len): (heapless::String<8>, _) =
+            crate::from_str_escaped(&json, &mut [0; 8]).unwrap();
+        assert_eq!(unescaped, value);
+        assert_eq!(len, json.len());
+
+        // Without an unescape buffer the escape sequence is left as is
+        assert_eq!(
+            crate::from_str::<&str>(&json),
+            Ok((r#"a\u0000b\u0000"#, json.len()))
+        );
+    }
+
+    #[test]
+    fn bytes_from_array() {
+        use crate::de::Error;
+        use core::fmt;
+        use serde::de::{self, Deserializer, Visitor};
+
+        /// Deserializes through `deserialize_bytes`, like `serde_bytes::ByteBuf`
+        #[derive(Debug, PartialEq)]
+        struct ByteBuf(heapless::Vec<u8, 8>);
+
+        impl<'de> de::Deserialize<'de> for ByteBuf {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct ByteBufVisitor;
+
+                impl<'de> Visitor<'de> for ByteBufVisitor {
+                    type Value = ByteBuf;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        formatter.write_str("bytes")
+                    }
+
+                    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+                        heapless::Vec::from_slice(v)
+                            .map(ByteBuf)
+                            .map_err(|_| E::invalid_length(v.len(), &self))
+                    }
+                }
+
+                deserializer.deserialize_bytes(ByteBufVisitor)
+            }
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Packet {
+            payload: ByteBuf,
+            name: heapless::String<8>,
+        }
+
+        let json = r#"{"payload": [1, 2, 3], "name": "\u0041"}"#;
+        let (packet, _) = crate::from_str_escaped::<Packet>(json, &mut [0; 4]).unwrap();
+        assert_eq!(packet.payload.0, [1, 2, 3]);
+        assert_eq!(packet.name, "A");
+
+        let scratch = &mut [0; 4];
+        assert_eq!(
+            crate::from_str_escaped::<ByteBuf>("[]", scratch).map(|(b, _)| b.0.len()),
+            Ok(0)
+        );
+        assert_eq!(
+            crate::from_str_escaped::<ByteBuf>("[1,2,3,4,5]", scratch),
+            Err(Error::ByteArrayIsTooLong)
+        );
+        assert_eq!(
+            crate::from_str_escaped::<ByteBuf>("[1,256]", scratch),
+            Err(Error::InvalidNumber)
+        );
+        assert_eq!(
+            crate::from_str_escaped::<ByteBuf>(r#""AQID""#, scratch),
+            Err(Error::InvalidType)
+        );
+
+        // Without a buffer to copy them into, bytes are still unsupported
+        assert_eq!(
+            crate::from_str::<ByteBuf>("[1,2,3]"),
+            Err(Error::BytesIsUnsupported)
+        );
+    }
+
+    #[test]
+    fn bytes_ref() {
+        use crate::str::BytesRef;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Packet<'a> {
+            #[serde(borrow)]
+            payload: BytesRef<'a>,
+        }
+
+        let json = r#"{"payload":"a\"b\u0000"}"#;
+        let (packet, _) = crate::from_str::<Packet<'_>>(json).unwrap();
+        assert_eq!(packet.payload, BytesRef(br#"a\"b\u0000"#));
+        assert_eq!(packet.payload.0.as_ptr(), json[12..].as_ptr());
+
+        // Even with an unescape buffer, the raw bytes are borrowed
+        let (packet, _) = crate::from_str_escaped::<Packet<'_>>(json, &mut [0; 16]).unwrap();
+        assert_eq!(packet.payload.0.as_ptr(), json[12..].as_ptr());
+
+        assert_eq!(
+            crate::from_str::<Packet<'_>>(r#"{"payload":1}"#),
+            Err(crate::de::Error::InvalidType)
+        );
+    }
+
+    #[test]
+    fn tuple_from_object() {
+        use crate::de::{Config, Error};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point(i8, i8);
+
+        let config = Config::default().tuple_from_object(true);
+        assert_eq!(
+            crate::from_str_with_config(r#"{"0":1,"1":2}"#, &config),
+            Ok(((1u8, 2u8), 13))
+        );
+        assert_eq!(
+            crate::from_str_with_config(r#"{ "0": -1, "1": 2 }"#, &config),
+            Ok((Point(-1, 2), 19))
+        );
+        // Arrays are still accepted
+        assert_eq!(
+            crate::from_str_with_config("[1,2]", &config),
+            Ok(((1u8, 2u8), 5))
+        );
+
+        // Keys must be the positions in order
+        assert!(crate::from_str_with_config::<(u8, u8)>(r#"{"1":2,"0":1}"#, &config).is_err());
+        assert!(crate::from_str_with_config::<(u8, u8)>(r#"{"0":1,"01":2}"#, &config).is_err());
+        assert!(crate::from_str_with_config::<(u8, u8)>(r#"{"0":1}"#, &config).is_err());
+        assert!(
+            crate::from_str_with_config::<(u8, u8)>(r#"{"0":1,"1":2,"2":3}"#, &config).is_err()
+        );
+
+        assert_eq!(
+            crate::from_str::<(u8, u8)>(r#"{"0":1,"1":2}"#),
+            Err(Error::InvalidType)
+        );
+    }
+
+    #[test]
+    fn many_structs() {
+        #[derive(Debug, Deserialize, PartialEq, serde_derive::Serialize)]
+        struct Item<'a> {
+            id: u16,
+            name: &'a str,
+        }
+
+        let names = ["a", r#"b\"c"#, r#"\\\\\\\\\\\\\\\\"#];
+        let items: heapless::Vec<Item<'_>, 256> = (0..256)
+            .map(|id| Item {
+                id,
+                name: names[id as usize % names.len()],
+            })
+            .collect();
+
+        let json = crate::to_string::<_, 16384>(&items).unwrap();
+        let (parsed, len): (heapless::Vec<Item<'_>, 256>, _) = crate::from_str(&json).unwrap();
+        assert_eq!(len, json.len());
+        assert_eq!(parsed.len(), items.len());
+        for (parsed, item) in parsed.iter().zip(&items) {
+            assert_eq!(parsed.id, item.id);
+            // Strings are borrowed still escaped
+            let escaped = crate::to_string::<_, 64>(item.name).unwrap();
+            assert_eq!(parsed.name, &escaped[1..escaped.len() - 1]);
+        }
+
+        // A long run of escaped backslashes followed by an escaped quote
+        let mut json = heapless::String::<4096>::new();
+        json.push('"').unwrap();
+        for _ in 0..2000 {
+            json.push('\\').unwrap();
+        }
+        json.push_str("\\\"\"").unwrap();
+        assert_eq!(
+            crate::from_str::<&str>(&json),
+            Ok((&json[1..json.len() - 1], json.len()))
+        );
     }
 }
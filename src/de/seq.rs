@@ -1,15 +1,21 @@
+use core::fmt;
+
 use serde::de;
 
+use crate::de::map::MapAccess;
 use crate::de::{Deserializer, Error, Result};
 
 pub(crate) struct SeqAccess<'a, 'b, 's> {
-    first: bool,
+    /// Number of elements so far
+    len: usize,
     de: &'a mut Deserializer<'b, 's>,
 }
 
 impl<'a, 'b, 's> SeqAccess<'a, 'b, 's> {
     pub fn new(de: &'a mut Deserializer<'b, 's>) -> Self {
-        SeqAccess { de, first: true }
+        // Objects in arrays aren't the outermost object
+        de.outer_field_mask = None;
+        SeqAccess { de, len: 0 }
     }
 }
 
@@ -26,15 +32,14 @@ impl<'a, 'de, 's> de::SeqAccess<'de> for SeqAccess<'a, 'de, 's> {
             .ok_or(Error::EofWhileParsingList)?
         {
             b']' => return Ok(None),
-            b',' => {
+            b',' if self.len > 0 => {
                 self.de.eat_char();
                 self.de
                     .parse_whitespace()
                     .ok_or(Error::EofWhileParsingValue)?
             }
             c => {
-                if self.first {
-                    self.first = false;
+                if self.len == 0 {
                     c
                 } else {
                     return Err(Error::ExpectedListCommaOrEnd);
@@ -43,9 +48,82 @@ impl<'a, 'de, 's> de::SeqAccess<'de> for SeqAccess<'a, 'de, 's> {
         };
 
         if peek == b']' {
+            if self.de.config.allow_trailing_commas {
+                return Ok(None);
+            }
             Err(Error::TrailingComma)
         } else {
+            self.len = self.de.check_elements(self.len)?;
             Ok(Some(seed.deserialize(&mut *self.de)?))
         }
     }
 }
+
+/// Reads the members of an object with the keys `"0"`, `"1"`, ... in that order as a sequence
+pub(crate) struct IndexedObjectAccess<'a, 'b, 's> {
+    map: MapAccess<'a, 'b, 's>,
+    index: usize,
+}
+
+impl<'a, 'b, 's> IndexedObjectAccess<'a, 'b, 's> {
+    pub fn new(de: &'a mut Deserializer<'b, 's>) -> Self {
+        IndexedObjectAccess {
+            map: MapAccess::new(de),
+            index: 0,
+        }
+    }
+}
+
+impl<'a, 'de, 's> de::SeqAccess<'de> for IndexedObjectAccess<'a, 'de, 's> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        use serde::de::MapAccess as _;
+
+        match self.map.next_key_seed(Index(self.index))? {
+            Some(()) => {
+                self.index += 1;
+                self.map.next_value_seed(seed).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Accepts only the object key for the element at this index
+struct Index(usize);
+
+impl<'de> de::DeserializeSeed<'de> for Index {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for Index {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "the key \"{}\"", self.0)
+    }
+
+    fn visit_str<E>(self, v: &str) -> core::result::Result<(), E>
+    where
+        E: de::Error,
+    {
+        // `parse` alone would also accept leading zeros and a `+` sign
+        let canonical = v.bytes().all(|c| c.is_ascii_digit()) && (v == "0" || !v.starts_with('0'));
+        if canonical && v.parse() == Ok(self.0) {
+            Ok(())
+        } else {
+            Err(E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+}
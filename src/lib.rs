@@ -30,6 +30,9 @@
 //!
 //! - The error type is a simple C like enum (less overhead, smaller memory footprint)
 //! - (De)serialization doesn't require memory allocations
+//! - With the optional `arrayvec` feature, `arrayvec::ArrayVec` and `arrayvec::ArrayString` can be
+//!   used instead of their `heapless` counterparts, including `ser::arrayvec::to_string` and
+//!   `ser::arrayvec::to_vec`
 //! - Deserialization of integers doesn't go through `u64`; instead the string is directly parsed
 //!   into the requested integer type. This avoids pulling in KBs of compiler intrinsics when
 //!   targeting a non 64-bit architecture.
@@ -67,6 +70,10 @@
 //! - Anything that involves dynamic memory allocation
 //!   - Like the dynamic [`Value`](https://docs.rs/serde_json/1.0.11/serde_json/enum.Value.html)
 //!     type
+//!   - The optional `alloc` feature only adds `ser::alloc::to_string` and `ser::alloc::to_vec`
+//!     for targets that do have a heap but don't want to size a `heapless` buffer up front. It
+//!     also enables serde's `alloc` feature, which internally tagged and adjacently tagged enums
+//!     need to be deserialized.
 //!
 //! # Minimum Supported Rust Version (MSRV)
 //!
@@ -79,16 +86,147 @@
 #![deny(warnings)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod de;
 pub mod ser;
 pub mod str;
+pub mod with;
 
 #[doc(inline)]
-pub use self::de::{from_slice, from_slice_escaped, from_str, from_str_escaped};
+pub use self::de::{
+    from_chunks, from_slice, from_slice_auto, from_slice_escaped, from_slice_value,
+    from_slice_with_config, from_str, from_str_auto, from_str_escaped, from_str_value,
+    from_str_with_config, validate,
+};
 #[doc(inline)]
-pub use self::ser::to_slice;
+pub use self::ser::{to_array, to_slice, to_slice_with_config};
 #[cfg(feature = "heapless")]
 pub use self::ser::{to_string, to_vec};
 
 #[cfg(feature = "heapless")]
 pub use heapless;
+
+#[cfg(feature = "arrayvec")]
+pub use arrayvec;
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Debug;
+
+    use serde::de::DeserializeOwned;
+    use serde_derive::{Deserialize, Serialize};
+
+    /// Serializes `value`, deserializes the output and checks that the result is `value` again
+    pub(crate) fn assert_round_trip<T>(value: T)
+    where
+        T: serde::Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let json = crate::to_vec::<_, 256>(&value).unwrap();
+        let (result, len) = crate::from_slice_escaped::<T>(&json, &mut [0; 256]).unwrap();
+        assert_eq!(len, json.len());
+        assert_eq!(result, value, "{}", core::str::from_utf8(&json).unwrap());
+    }
+
+    #[test]
+    fn round_trip_scalars() {
+        assert_round_trip(true);
+        assert_round_trip(false);
+        assert_round_trip(());
+
+        assert_round_trip(i8::MIN);
+        assert_round_trip(i8::MAX);
+        assert_round_trip(i16::MIN);
+        assert_round_trip(i16::MAX);
+        assert_round_trip(i32::MIN);
+        assert_round_trip(i32::MAX);
+        assert_round_trip(i64::MIN);
+        assert_round_trip(i64::MAX);
+        assert_round_trip(0u8);
+        assert_round_trip(u8::MAX);
+        assert_round_trip(u16::MAX);
+        assert_round_trip(u32::MAX);
+        assert_round_trip(u64::MAX);
+
+        for v in [0.0, -1.5, 0.1, f32::MIN_POSITIVE, f32::MAX, f32::EPSILON] {
+            assert_round_trip(v);
+        }
+        for v in [0.0, -1.5, 0.1, f64::MIN_POSITIVE, f64::MAX, 1e-300] {
+            assert_round_trip(v);
+        }
+
+        for c in ['a', '"', '\\', '\n', '\u{0}', 'é', '\u{2028}', '😀'] {
+            assert_round_trip(c);
+        }
+
+        for s in [
+            "",
+            "plain",
+            "quote \" and \\ backslash",
+            "tab\tand\u{1f}",
+            "ünïcödé 😀",
+        ] {
+            let mut string = heapless::String::<32>::new();
+            string.push_str(s).unwrap();
+            assert_round_trip(string);
+        }
+
+        assert_round_trip(None::<u8>);
+        assert_round_trip(Some(5u8));
+    }
+
+    #[test]
+    fn round_trip_tuples() {
+        assert_round_trip((1u8,));
+        assert_round_trip((1u8, -2i64, true));
+        assert_round_trip(((1u8, 2u8), [3u16, 4], Some((5i8, None::<bool>))));
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Pair(u32, char);
+        assert_round_trip(Pair(7, 'x'));
+        assert_round_trip([Pair(1, 'a'), Pair(2, 'b')]);
+    }
+
+    #[test]
+    fn round_trip_enums() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle(f32),
+            Rectangle(u16, u16),
+            Polygon { sides: u8, regular: bool },
+        }
+
+        assert_round_trip(Shape::Empty);
+        assert_round_trip(Shape::Circle(0.5));
+        assert_round_trip(Shape::Rectangle(3, 4));
+        assert_round_trip(Shape::Polygon {
+            sides: 6,
+            regular: true,
+        });
+        assert_round_trip([Shape::Empty, Shape::Circle(-2.0)]);
+        assert_round_trip((Some(Shape::Rectangle(1, 1)), None::<Shape>));
+    }
+
+    #[test]
+    fn round_trip_solidus() {
+        use crate::ser::Config;
+
+        let value = "</script> a/b //";
+        for (escape, expected) in [
+            (false, r#""</script> a/b //""#),
+            (true, r#""<\/script> a\/b \/\/""#),
+        ] {
+            let mut buf = [0; 32];
+            let config = Config::default().escape_solidus(escape);
+            let len = crate::to_slice_with_config(value, &mut buf, &config).unwrap();
+            assert_eq!(&buf[..len], expected.as_bytes());
+
+            let (result, _) =
+                crate::from_slice_escaped::<heapless::String<32>>(&buf[..len], &mut [0; 32])
+                    .unwrap();
+            assert_eq!(result, value);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Serialize a Rust data structure into heap-allocated JSON data
+
+use ::alloc::string::String;
+use ::alloc::vec::Vec;
+
+use serde::ser;
+
+use crate::ser::{Result, Serializer};
+
+/// Serializes the given data structure as a string of JSON text
+pub fn to_string<T>(value: &T) -> Result<String>
+where
+    T: ser::Serialize + ?Sized,
+{
+    Ok(unsafe { String::from_utf8_unchecked(to_vec(value)?) })
+}
+
+/// Serializes the given data structure as a JSON byte vector
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut ser = Serializer::with_backend(Vec::new());
+    value.serialize(&mut ser)?;
+    Ok(ser.into_backend())
+}
@@ -0,0 +1,29 @@
+//! Serialize a Rust data structure into JSON data stored in `arrayvec` types
+
+use core::str;
+
+use ::arrayvec::{ArrayString, ArrayVec};
+use serde::ser;
+
+use crate::ser::{Result, Serializer};
+
+/// Serializes the given data structure as a string of JSON text
+pub fn to_string<T, const N: usize>(value: &T) -> Result<ArrayString<N>>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let vec = to_vec::<T, N>(value)?;
+    let mut string = ArrayString::new();
+    string.push_str(unsafe { str::from_utf8_unchecked(&vec) });
+    Ok(string)
+}
+
+/// Serializes the given data structure as a JSON byte vector
+pub fn to_vec<T, const N: usize>(value: &T) -> Result<ArrayVec<u8, N>>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut ser = Serializer::with_backend(ArrayVec::<u8, N>::new());
+    value.serialize(&mut ser)?;
+    Ok(ser.into_backend())
+}
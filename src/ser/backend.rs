@@ -0,0 +1,409 @@
+//! Sinks the `Serializer` writes JSON data to
+
+use core::fmt;
+use core::hash::Hasher;
+
+use crate::ser::{Error, Result};
+
+/// A sink for serialized JSON data.
+///
+/// Writes are all-or-nothing: if `extend_from_slice` can't store all of `other`, it must leave the
+/// backend unchanged and return [`Error::BufferFull`].
+pub trait SerializerBackend {
+    /// Append a single byte
+    fn push(&mut self, c: u8) -> Result<()>;
+
+    /// Append all of `other`
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()>;
+
+    /// Return the amount of data written so far
+    fn end(&self) -> usize;
+
+    /// Return how many more bytes can be written, `usize::MAX` by default for backends without a
+    /// fixed capacity
+    fn remaining(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Return the data written so far, if the backend keeps it around
+    ///
+    /// [`Config::sort_struct_fields`](crate::ser::Config::sort_struct_fields) rearranges the
+    /// output in place and fails with [`Error::SortingUnsupported`] for backends returning `None`,
+    /// which is the default.
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+impl<B: SerializerBackend + ?Sized> SerializerBackend for &mut B {
+    fn push(&mut self, c: u8) -> Result<()> {
+        (**self).push(c)
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        (**self).extend_from_slice(other)
+    }
+
+    fn end(&self) -> usize {
+        (**self).end()
+    }
+
+    fn remaining(&self) -> usize {
+        (**self).remaining()
+    }
+
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        (**self).written_mut()
+    }
+}
+
+/// Writes into a fixed-size byte slice
+pub struct Slice<'a> {
+    buf: &'a mut [u8],
+    index: usize,
+}
+
+impl<'a> Slice<'a> {
+    /// Create a new `Slice` writing from the start of `buf`
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Slice { buf, index: 0 }
+    }
+
+    unsafe fn push_unchecked(&mut self, c: u8) {
+        *self.buf.get_unchecked_mut(self.index) = c;
+        self.index += 1;
+    }
+}
+
+impl<'a> SerializerBackend for Slice<'a> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        if self.index < self.buf.len() {
+            unsafe { self.push_unchecked(c) };
+            Ok(())
+        } else {
+            Err(Error::BufferFull)
+        }
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        match self.index.checked_add(other.len()) {
+            Some(end) if end <= self.buf.len() => {
+                for c in other {
+                    unsafe { self.push_unchecked(*c) };
+                }
+                Ok(())
+            }
+            // won't fit in the buf; don't modify anything and return an error
+            _ => Err(Error::BufferFull),
+        }
+    }
+
+    fn end(&self) -> usize {
+        self.index
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.index
+    }
+
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        Some(&mut self.buf[..self.index])
+    }
+}
+
+/// Discards the data and only counts its length
+#[derive(Debug, Default)]
+pub struct Counter {
+    count: usize,
+}
+
+impl Counter {
+    /// Create a new `Counter` starting at zero
+    pub fn new() -> Self {
+        Counter { count: 0 }
+    }
+}
+
+impl SerializerBackend for Counter {
+    fn push(&mut self, c: u8) -> Result<()> {
+        self.extend_from_slice(&[c])
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        self.count = self
+            .count
+            .checked_add(other.len())
+            .ok_or(Error::BufferFull)?;
+        Ok(())
+    }
+
+    fn end(&self) -> usize {
+        self.count
+    }
+
+    fn remaining(&self) -> usize {
+        usize::MAX - self.count
+    }
+}
+
+/// Feeds the data into a [`core::hash::Hasher`], e.g. a CRC implementation, and discards it
+///
+/// Wrap it in a [`Tee`] to keep the output as well.
+#[derive(Debug, Default)]
+pub struct Hashing<H> {
+    hasher: H,
+    count: usize,
+}
+
+impl<H: Hasher> Hashing<H> {
+    /// Create a new `Hashing` backend feeding `hasher`
+    pub fn new(hasher: H) -> Self {
+        Hashing { hasher, count: 0 }
+    }
+
+    /// Return the hasher
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Return the wrapped hasher
+    pub fn into_inner(self) -> H {
+        self.hasher
+    }
+}
+
+impl<H: Hasher> SerializerBackend for Hashing<H> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        self.extend_from_slice(&[c])
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        self.count = self
+            .count
+            .checked_add(other.len())
+            .ok_or(Error::BufferFull)?;
+        self.hasher.write(other);
+        Ok(())
+    }
+
+    fn end(&self) -> usize {
+        self.count
+    }
+}
+
+/// Writes the same data into two backends, e.g. an output buffer and a checksum
+///
+/// [`end`](SerializerBackend::end) reports the first backend. Writes check the
+/// [`remaining`](SerializerBackend::remaining) space of both backends before touching either, so
+/// a full backend leaves both unchanged. Only a backend that fails for another reason, like a
+/// [`FmtWriter`], can leave the first backend with a write the second didn't take.
+#[derive(Debug, Default)]
+pub struct Tee<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: SerializerBackend, B: SerializerBackend> Tee<A, B> {
+    /// Create a new `Tee` writing into `first` and `second`
+    pub fn new(first: A, second: B) -> Self {
+        Tee { first, second }
+    }
+
+    /// Return the wrapped backends
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: SerializerBackend, B: SerializerBackend> SerializerBackend for Tee<A, B> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        if self.first.remaining() == 0 || self.second.remaining() == 0 {
+            return Err(Error::BufferFull);
+        }
+        self.first.push(c)?;
+        self.second.push(c)
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        if self.first.remaining() < other.len() || self.second.remaining() < other.len() {
+            return Err(Error::BufferFull);
+        }
+        self.first.extend_from_slice(other)?;
+        self.second.extend_from_slice(other)
+    }
+
+    fn end(&self) -> usize {
+        self.first.end()
+    }
+
+    fn remaining(&self) -> usize {
+        self.first.remaining().min(self.second.remaining())
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> SerializerBackend for heapless::Vec<u8, N> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        Ok(heapless::Vec::push(self, c)?)
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        Ok(heapless::Vec::extend_from_slice(self, other)?)
+    }
+
+    fn end(&self) -> usize {
+        self.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<const N: usize> SerializerBackend for arrayvec::ArrayVec<u8, N> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        self.try_push(c).map_err(|_| Error::BufferFull)
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        self.try_extend_from_slice(other)
+            .map_err(|_| Error::BufferFull)
+    }
+
+    fn end(&self) -> usize {
+        self.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.remaining_capacity()
+    }
+
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        Some(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl SerializerBackend for ::alloc::vec::Vec<u8> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        ::alloc::vec::Vec::push(self, c);
+        Ok(())
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        ::alloc::vec::Vec::extend_from_slice(self, other);
+        Ok(())
+    }
+
+    fn end(&self) -> usize {
+        self.len()
+    }
+
+    fn written_mut(&mut self) -> Option<&mut [u8]> {
+        Some(self)
+    }
+}
+
+/// Returns the length of the UTF-8 sequence starting with `first`, which must be a valid first byte
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    }
+}
+
+/// Writes into a [`core::fmt::Write`] implementation, e.g. a `core::fmt::Formatter`
+///
+/// The output must be valid UTF-8, which holds for all output of the `Serializer` except raw bytes
+/// passed to `serialize_bytes`; other bytes fail with [`Error::InvalidUtf8`]. A character split
+/// across writes is passed on once it's complete. A failing writer is reported as
+/// [`Error::WriteFailed`].
+pub struct FmtWriter<W> {
+    writer: W,
+    written: usize,
+    /// The start of a character whose remaining bytes haven't been written yet
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl<W: fmt::Write> FmtWriter<W> {
+    /// Create a new `FmtWriter` wrapping `writer`
+    pub fn new(writer: W) -> Self {
+        FmtWriter {
+            writer,
+            written: 0,
+            pending: [0; 4],
+            pending_len: 0,
+        }
+    }
+
+    /// Return the wrapped writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: fmt::Write> SerializerBackend for FmtWriter<W> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        self.extend_from_slice(&[c])
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        // Complete the pending character first
+        let mut pending = self.pending;
+        let mut pending_len = self.pending_len;
+        let mut rest = other;
+        if pending_len > 0 {
+            let len = utf8_len(pending[0]);
+            let take = rest.len().min(len - pending_len);
+            pending[pending_len..pending_len + take].copy_from_slice(&rest[..take]);
+            pending_len += take;
+            rest = &rest[take..];
+            if pending_len < len {
+                // Still incomplete, as long as it's the start of a valid character
+                return match core::str::from_utf8(&pending[..pending_len]) {
+                    Err(e) if e.error_len().is_none() => {
+                        self.pending = pending;
+                        self.pending_len = pending_len;
+                        self.written += other.len();
+                        Ok(())
+                    }
+                    _ => Err(Error::InvalidUtf8),
+                };
+            }
+        }
+        let head = match core::str::from_utf8(&pending[..pending_len]) {
+            Ok(head) => head,
+            Err(_) => return Err(Error::InvalidUtf8),
+        };
+
+        // Keep an incomplete character at the end for the next write
+        let (body, tail) = match core::str::from_utf8(rest) {
+            Ok(body) => (body, &[][..]),
+            Err(e) if e.error_len().is_none() => {
+                let (body, tail) = rest.split_at(e.valid_up_to());
+                (unsafe { core::str::from_utf8_unchecked(body) }, tail)
+            }
+            Err(_) => return Err(Error::InvalidUtf8),
+        };
+
+        self.writer.write_str(head).or(Err(Error::WriteFailed))?;
+        self.pending_len = 0;
+        self.writer.write_str(body).or(Err(Error::WriteFailed))?;
+        self.pending[..tail.len()].copy_from_slice(tail);
+        self.pending_len = tail.len();
+        self.written += other.len();
+        Ok(())
+    }
+
+    fn end(&self) -> usize {
+        self.written
+    }
+}
@@ -0,0 +1,109 @@
+//! Serialize a Rust data structure block by block
+
+use serde::ser;
+
+use crate::ser::{Error, Result, Serializer, SerializerBackend};
+
+/// Discards the first `skip` bytes of output and writes as much of the rest as fits into `buf`
+///
+/// Unlike the public backends, writes may be partial: the window is filled up to the last byte
+/// before `BufferFull` is returned.
+pub(crate) struct Window<'a> {
+    buf: &'a mut [u8],
+    skip: usize,
+    index: usize,
+}
+
+impl<'a> Window<'a> {
+    pub(crate) fn new(buf: &'a mut [u8], skip: usize) -> Self {
+        Window {
+            buf,
+            skip,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> SerializerBackend for Window<'a> {
+    fn push(&mut self, c: u8) -> Result<()> {
+        self.extend_from_slice(&[c])
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        let skipped = self.skip.min(other.len());
+        self.skip -= skipped;
+        let other = &other[skipped..];
+
+        let len = other.len().min(self.buf.len() - self.index);
+        self.buf[self.index..self.index + len].copy_from_slice(&other[..len]);
+        self.index += len;
+
+        if len < other.len() {
+            Err(Error::BufferFull)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn end(&self) -> usize {
+        self.index
+    }
+}
+
+/// Position in the serialized output at which the next block starts
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct ResumeToken(usize);
+
+/// Serializes a value into consecutive fixed-size blocks, e.g. for CoAP block-wise transfer.
+///
+/// Serde drives serialization from the top, so every block serializes the value again from the
+/// start, discarding the output of the previous blocks. The value must therefore serialize to the
+/// same output every time.
+///
+/// ```
+/// # use serde_json_core::ser::{BlockSerializer, ResumeToken};
+/// let value = ["block", "wise", "transfer"];
+/// let block_serializer = BlockSerializer::new(&value);
+///
+/// let mut json = heapless::Vec::<u8, 64>::new();
+/// let mut resume = ResumeToken::default();
+/// loop {
+///     let mut block = [0; 8];
+///     let (len, next) = block_serializer.serialize_block(&mut block, resume).unwrap();
+///     json.extend_from_slice(&block[..len]).unwrap();
+///     match next {
+///         Some(token) => resume = token,
+///         None => break,
+///     }
+/// }
+/// assert_eq!(json, br#"["block","wise","transfer"]"#);
+/// ```
+pub struct BlockSerializer<'a, T: ?Sized> {
+    value: &'a T,
+}
+
+impl<'a, T> BlockSerializer<'a, T>
+where
+    T: ser::Serialize + ?Sized,
+{
+    /// Create a new `BlockSerializer` for `value`
+    pub fn new(value: &'a T) -> Self {
+        BlockSerializer { value }
+    }
+
+    /// Serializes the block starting at `resume` into `buf`, which must not be empty.
+    ///
+    /// Returns the number of bytes written and, if the output continues past `buf`, the token to
+    /// pass in for the next block.
+    pub fn serialize_block(
+        &self,
+        buf: &mut [u8],
+        resume: ResumeToken,
+    ) -> Result<(usize, Option<ResumeToken>)> {
+        let mut ser = Serializer::with_backend(Window::new(buf, resume.0));
+        match self.value.serialize(&mut ser) {
+            Err(Error::BufferFull) => Ok((ser.end(), Some(ResumeToken(resume.0 + ser.end())))),
+            result => result.map(|()| (ser.end(), None)),
+        }
+    }
+}
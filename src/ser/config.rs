@@ -0,0 +1,147 @@
+//! Options controlling the produced JSON
+
+/// How to serialize NaN and infinite floats, which JSON numbers can't represent
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NonFiniteFloat {
+    /// Serialize them as `null`
+    #[default]
+    Null,
+
+    /// Serialize them as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`
+    Quoted,
+}
+
+/// Serializer configuration
+///
+/// The default produces compact JSON, like [`to_slice`](crate::to_slice). Options are enabled
+/// with the builder methods:
+///
+/// ```
+/// # use serde_json_core::ser::Config;
+/// let config = Config::default().pretty(true).escape_solidus(true);
+///
+/// let mut buf = [0; 32];
+/// let len = serde_json_core::ser::to_slice_with_config(&["a/b"], &mut buf, &config).unwrap();
+/// assert_eq!(&buf[..len], b"[\n  \"a\\/b\"\n]");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Config {
+    pub(crate) pretty: bool,
+    pub(crate) indent: &'static str,
+    pub(crate) skip_none: bool,
+    pub(crate) skip_unit: bool,
+    pub(crate) escape_non_ascii: bool,
+    pub(crate) escape_solidus: bool,
+    pub(crate) escape_line_separators: bool,
+    pub(crate) non_finite: NonFiniteFloat,
+    pub(crate) collapse_empty_struct_variants: bool,
+    pub(crate) max_depth: usize,
+    pub(crate) sort_struct_fields: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            pretty: false,
+            indent: "  ",
+            skip_none: false,
+            skip_unit: false,
+            escape_non_ascii: false,
+            escape_solidus: false,
+            escape_line_separators: false,
+            non_finite: NonFiniteFloat::Null,
+            collapse_empty_struct_variants: false,
+            max_depth: usize::MAX,
+            sort_struct_fields: false,
+        }
+    }
+}
+
+impl Config {
+    /// Put every array element and object member on its own, indented line
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Indent each nesting level with `indent` when pretty-printing; two spaces by default
+    pub fn indent(mut self, indent: &'static str) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Leave out struct fields whose value is `None` instead of serializing them as `null`
+    pub fn skip_none(mut self, skip: bool) -> Self {
+        self.skip_none = skip;
+        self
+    }
+
+    /// Leave out struct fields whose value is `()` or a unit struct, like a `PhantomData` marker,
+    /// instead of serializing them as `null`
+    pub fn skip_unit(mut self, skip: bool) -> Self {
+        self.skip_unit = skip;
+        self
+    }
+
+    /// Escape all non-ASCII characters in strings as `\uXXXX`, using surrogate pairs where needed
+    pub fn escape_non_ascii(mut self, escape: bool) -> Self {
+        self.escape_non_ascii = escape;
+        self
+    }
+
+    /// Escape `/` in strings as `\/`, e.g. to embed the output in an HTML `<script>` tag
+    pub fn escape_solidus(mut self, escape: bool) -> Self {
+        self.escape_solidus = escape;
+        self
+    }
+
+    /// Escape U+2028 LINE SEPARATOR and U+2029 PARAGRAPH SEPARATOR as `\u2028` and `\u2029`
+    ///
+    /// See [`Serializer::escape_line_separators`](crate::ser::Serializer::escape_line_separators).
+    pub fn escape_line_separators(mut self, escape: bool) -> Self {
+        self.escape_line_separators = escape;
+        self
+    }
+
+    /// Choose how NaN and infinite floats are serialized
+    pub fn non_finite(mut self, non_finite: NonFiniteFloat) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Serialize struct variants without any fields, like `Variant {}`, as `"Variant"` instead of
+    /// `{"Variant":{}}`
+    ///
+    /// This also applies to variants whose fields were all left out by [`Config::skip_none`] or
+    /// [`Config::skip_unit`].
+    pub fn collapse_empty_struct_variants(mut self, collapse: bool) -> Self {
+        self.collapse_empty_struct_variants = collapse;
+        self
+    }
+
+    /// Fail with [`Error::RecursionLimitExceeded`](crate::ser::Error::RecursionLimitExceeded)
+    /// instead of nesting arrays and objects deeper than `depth`; unlimited by default
+    ///
+    /// This bounds the stack usage of serializing recursive data structures.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Write struct fields ordered by name instead of in declaration order
+    ///
+    /// Together with the compact output and minimal string escaping this produces
+    /// [canonical JSON](https://gibson042.github.io/canonicaljson-spec/) for structs, e.g. to sign or
+    /// hash it. Names are compared bytewise, which is code point order for names without escapes.
+    /// Map entries keep their order.
+    ///
+    /// Fields are moved into place within the output after they're written, so this fails with
+    /// [`Error::SortingUnsupported`](crate::ser::Error::SortingUnsupported) for backends that
+    /// don't keep their data, like [`FmtWriter`](crate::ser::FmtWriter).
+    pub fn sort_struct_fields(mut self, sort: bool) -> Self {
+        self.sort_struct_fields = sort;
+        self
+    }
+}
@@ -0,0 +1,46 @@
+//! Track the largest serialized size across many values
+
+use serde::ser;
+
+use crate::ser::{Counter, Result, Serializer};
+
+/// Records the largest serialized length of all values passed to it.
+///
+/// Serializing a representative set of messages at runtime gives an empirical lower bound for the
+/// size of a static buffer that can hold all of them.
+///
+/// ```
+/// # use serde_json_core::ser::HighWaterMark;
+/// let mut mark = HighWaterMark::new();
+/// assert_eq!(mark.serialize(&[1, 2, 3]), Ok(7));
+/// assert_eq!(mark.serialize("hello"), Ok(7));
+/// assert_eq!(mark.serialize(&true), Ok(4));
+/// assert_eq!(mark.high_water_mark(), 7);
+/// ```
+#[derive(Debug, Default)]
+pub struct HighWaterMark {
+    max: usize,
+}
+
+impl HighWaterMark {
+    /// Create a new `HighWaterMark` that hasn't seen any values yet
+    pub fn new() -> Self {
+        HighWaterMark { max: 0 }
+    }
+
+    /// Serializes `value` without storing the output and returns its length
+    pub fn serialize<T>(&mut self, value: &T) -> Result<usize>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        let mut ser = Serializer::with_backend(Counter::new());
+        value.serialize(&mut ser)?;
+        self.max = self.max.max(ser.end());
+        Ok(ser.end())
+    }
+
+    /// Return the largest length returned by `serialize` so far
+    pub fn high_water_mark(&self) -> usize {
+        self.max
+    }
+}
@@ -1,38 +1,60 @@
-use serde::ser;
+use core::fmt;
 
-use crate::ser::{Error, Result, Serializer};
+use serde::ser::{self, Impossible};
 
-pub struct SerializeMap<'a, 'b> {
-    ser: &'a mut Serializer<'b>,
+use crate::ser::{Error, Result, Serializer, SerializerBackend, Slice};
+
+pub struct SerializeMap<'a, 'b, B = Slice<'b>> {
+    ser: &'a mut Serializer<'b, B>,
     first: bool,
 }
 
-impl<'a, 'b: 'a> SerializeMap<'a, 'b> {
-    pub(crate) fn new(ser: &'a mut Serializer<'b>) -> Self {
+impl<'a, 'b: 'a, B: SerializerBackend> SerializeMap<'a, 'b, B> {
+    pub(crate) fn new(ser: &'a mut Serializer<'b, B>) -> Self {
         SerializeMap { ser, first: true }
     }
 }
 
-impl<'a, 'b: 'a> ser::SerializeMap for SerializeMap<'a, 'b> {
+/// Writes a JSON object one member at a time; see [`Serializer::begin_object`]
+pub struct ObjectBuilder<'a, 'b, B = Slice<'b>>(SerializeMap<'a, 'b, B>);
+
+impl<'a, 'b: 'a, B: SerializerBackend> ObjectBuilder<'a, 'b, B> {
+    pub(crate) fn new(map: SerializeMap<'a, 'b, B>) -> Self {
+        ObjectBuilder(map)
+    }
+
+    /// Append a member named `key` with `value` to the object
+    pub fn field<V>(&mut self, key: &str, value: &V) -> Result<()>
+    where
+        V: ser::Serialize + ?Sized,
+    {
+        ser::SerializeMap::serialize_entry(&mut self.0, key, value)
+    }
+
+    /// Close the object
+    pub fn finish(self) -> Result<()> {
+        ser::SerializeMap::end(self.0)
+    }
+}
+
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeMap for SerializeMap<'a, 'b, B> {
     type Ok = ();
     type Error = Error;
 
     fn end(self) -> Result<Self::Ok> {
-        self.ser.push(b'}')?;
-        Ok(())
+        self.ser.end_compound(b'}', self.first)
     }
 
     fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
         T: ser::Serialize + ?Sized,
     {
-        if !self.first {
-            self.ser.push(b',')?;
-        }
+        self.ser.begin_element(self.first)?;
         self.first = false;
-        key.serialize(&mut *self.ser)?;
-        self.ser.extend_from_slice(b":")?;
-        Ok(())
+        key.serialize(MapKeySerializer {
+            ser: &mut *self.ser,
+        })?;
+        self.ser.key_separator()
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
@@ -43,3 +65,198 @@ impl<'a, 'b: 'a> ser::SerializeMap for SerializeMap<'a, 'b> {
         Ok(())
     }
 }
+
+/// Serializes map keys, which JSON requires to be strings.
+///
+/// Strings, chars and unit variants are serialized as usual, while integers and booleans are
+/// wrapped in quotes. Anything else is rejected with `Error::KeyMustBeAString`.
+struct MapKeySerializer<'a, 'b, B> {
+    ser: &'a mut Serializer<'b, B>,
+}
+
+macro_rules! serialize_quoted {
+    ($self:ident, $serialize_fn:ident, $v:expr) => {{
+        $self.ser.push(b'"')?;
+        ser::Serializer::$serialize_fn(&mut *$self.ser, $v)?;
+        $self.ser.push(b'"')
+    }};
+}
+
+impl<'a, 'b: 'a, B: SerializerBackend> ser::Serializer for MapKeySerializer<'a, 'b, B> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_bool, v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_i8, v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_i16, v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_i32, v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_i64, v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_u8, v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_u16, v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_u32, v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_u64, v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_i128, v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok> {
+        serialize_quoted!(self, serialize_u128, v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        ser::Serializer::serialize_str(self.ser, v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        if name == crate::str::EscapedStr::NAME {
+            ser::Serializer::serialize_newtype_struct(self.ser, name, value)
+        } else {
+            value.serialize(self)
+        }
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::KeyMustBeAString)
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: fmt::Display + ?Sized,
+    {
+        ser::Serializer::collect_str(self.ser, value)
+    }
+}
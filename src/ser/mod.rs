@@ -1,20 +1,36 @@
 //! Serialize a Rust data structure into JSON data
 
+use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 use core::{fmt, str};
 
 use serde::ser;
-use serde::ser::SerializeStruct as _;
 use serde::Serialize;
 
 #[cfg(feature = "heapless")]
 use heapless::{String, Vec};
 
+pub use self::backend::{Counter, FmtWriter, Hashing, SerializerBackend, Slice, Tee};
+pub use self::block::{BlockSerializer, ResumeToken};
+pub use self::config::{Config, NonFiniteFloat};
+pub use self::high_water_mark::HighWaterMark;
+pub use self::map::ObjectBuilder;
 use self::map::SerializeMap;
-use self::seq::SerializeSeq;
+pub use self::number_format::{Fixed, NumberFormat, Ryu, Trimmed};
+pub use self::seq::ArrayBuilder;
+use self::seq::{SerializeSeq, SerializeTupleVariant};
 use self::struct_::{SerializeStruct, SerializeStructVariant};
 
+#[cfg(feature = "alloc")]
+pub mod alloc;
+#[cfg(feature = "arrayvec")]
+pub mod arrayvec;
+pub mod backend;
+mod block;
+mod config;
+mod high_water_mark;
 mod map;
+mod number_format;
 mod seq;
 mod struct_;
 
@@ -28,6 +44,25 @@ pub type Result<T> = ::core::result::Result<T, Error>;
 pub enum Error {
     /// Buffer is full
     BufferFull,
+
+    /// Map key can't be represented as a JSON string.
+    KeyMustBeAString,
+
+    /// Arrays and objects are nested deeper than [`Config::max_depth`] allows.
+    RecursionLimitExceeded,
+
+    /// [`Config::sort_struct_fields`] needs a backend that supports
+    /// [`SerializerBackend::written_mut`] and field values that serialize to valid JSON.
+    SortingUnsupported,
+
+    /// A `Serialize` implementation reported an error, whose message was discarded.
+    CustomError,
+
+    /// The [`core::fmt::Write`] implementation of a [`FmtWriter`] failed.
+    WriteFailed,
+
+    /// Raw bytes passed to `serialize_bytes` aren't valid UTF-8, which [`FmtWriter`] can't write.
+    InvalidUtf8,
 }
 
 impl From<()> for Error {
@@ -46,53 +81,261 @@ impl serde::ser::StdError for Error {}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Buffer is full")
+        write!(
+            f,
+            "{}",
+            match self {
+                Error::BufferFull => "Buffer is full",
+                Error::KeyMustBeAString => "Map key can't be represented as a JSON string",
+                Error::RecursionLimitExceeded => "Recursion limit exceeded",
+                Error::SortingUnsupported => "Struct fields can't be sorted",
+                Error::CustomError => "Custom error",
+                Error::WriteFailed => "Writer failed",
+                Error::InvalidUtf8 => "Output is not valid UTF-8",
+            }
+        )
     }
 }
 
 /// A structure that serializes Rust values as JSON into a buffer.
-pub struct Serializer<'a> {
-    buf: &'a mut [u8],
-    current_length: usize,
+///
+/// By default the output is written into a byte slice; [`Serializer::with_backend`] writes into
+/// any other [`SerializerBackend`].
+pub struct Serializer<'a, B = Slice<'a>> {
+    backend: B,
+    config: Config,
+    number_format: &'a dyn NumberFormat,
+    depth: usize,
+    /// Separate groups of three digits of integers with commas, see [`to_display_string`]
+    group_digits: bool,
+    /// Keys of the outermost struct's fields to write, see [`Serializer::with_field_mask`]
+    field_mask: Option<&'a [&'a str]>,
+    _buf: PhantomData<&'a mut [u8]>,
 }
 
 impl<'a> Serializer<'a> {
     /// Create a new `Serializer`
     pub fn new(buf: &'a mut [u8]) -> Self {
+        Serializer::with_backend(Slice::new(buf))
+    }
+}
+
+impl<'a, B: SerializerBackend> Serializer<'a, B> {
+    /// Create a new `Serializer` writing into `backend`
+    pub fn with_backend(backend: B) -> Self {
         Serializer {
-            buf,
-            current_length: 0,
+            backend,
+            config: Config::default(),
+            number_format: &Ryu,
+            depth: 0,
+            group_digits: false,
+            field_mask: None,
+            _buf: PhantomData,
         }
     }
 
+    /// Use `config` instead of the default compact output
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Write floats with `format` instead of [`Ryu`]
+    pub fn with_number_format(mut self, format: &'a dyn NumberFormat) -> Self {
+        self.number_format = format;
+        self
+    }
+
+    /// Only write the fields of the outermost struct whose keys are in `fields`
+    ///
+    /// Nested structs, maps and struct variants are written in full.
+    pub fn with_field_mask(mut self, fields: &'a [&'a str]) -> Self {
+        self.field_mask = Some(fields);
+        self
+    }
+
+    /// Whether the field `key` of a struct is written, see [`Serializer::with_field_mask`]
+    pub(crate) fn selects(&self, key: &str) -> bool {
+        self.depth != 1 || self.field_mask.map_or(true, |fields| fields.contains(&key))
+    }
+
+    /// Always escape U+2028 LINE SEPARATOR and U+2029 PARAGRAPH SEPARATOR as `\u2028` and
+    /// `\u2029`.
+    ///
+    /// Both are valid unescaped in JSON strings, but are line terminators in JavaScript, so output
+    /// embedded in a script or evaluated by a non-conforming parser breaks unless they're escaped.
+    pub fn escape_line_separators(mut self, escape: bool) -> Self {
+        self.config.escape_line_separators = escape;
+        self
+    }
+
+    /// Start writing an array whose elements are pushed one at a time
+    ///
+    /// This serializes elements produced by a loop without collecting them first.
+    ///
+    /// ```
+    /// # use serde_json_core::ser::Serializer;
+    /// let mut buf = [0; 16];
+    /// let mut ser = Serializer::new(&mut buf);
+    /// let mut array = ser.begin_array().unwrap();
+    /// for i in 1..4 {
+    ///     array.push_element(&(i * i)).unwrap();
+    /// }
+    /// array.finish().unwrap();
+    /// let len = ser.end();
+    /// assert_eq!(&buf[..len], b"[1,4,9]");
+    /// ```
+    pub fn begin_array(&mut self) -> Result<ArrayBuilder<'_, 'a, B>> {
+        self.begin_compound(b'[')?;
+        Ok(ArrayBuilder::new(SerializeSeq::new(self)))
+    }
+
+    /// Start writing an object whose members are added one at a time
+    ///
+    /// This serializes objects whose members are only known at runtime, without a struct. Keys are
+    /// escaped like any other string; duplicates are not detected.
+    ///
+    /// ```
+    /// # use serde_json_core::ser::Serializer;
+    /// let mut buf = [0; 32];
+    /// let mut ser = Serializer::new(&mut buf);
+    /// let mut object = ser.begin_object().unwrap();
+    /// object.field("id", &7).unwrap();
+    /// object.field("name", "pump").unwrap();
+    /// object.finish().unwrap();
+    /// let len = ser.end();
+    /// assert_eq!(&buf[..len], br#"{"id":7,"name":"pump"}"#);
+    /// ```
+    pub fn begin_object(&mut self) -> Result<ObjectBuilder<'_, 'a, B>> {
+        self.begin_compound(b'{')?;
+        Ok(ObjectBuilder::new(SerializeMap::new(self)))
+    }
+
     /// Return the current amount of serialized data in the buffer
     pub fn end(&self) -> usize {
-        self.current_length
+        self.backend.end()
+    }
+
+    /// Return how many more bytes fit into the buffer
+    ///
+    /// Custom `Serialize` implementations can check this to give up early or pick a more compact
+    /// encoding. Backends without a fixed capacity, like a [`FmtWriter`], return `usize::MAX`.
+    pub fn remaining(&self) -> usize {
+        self.backend.remaining()
+    }
+
+    /// Return the backend the data was serialized into
+    pub fn into_backend(self) -> B {
+        self.backend
     }
 
     fn push(&mut self, c: u8) -> Result<()> {
-        if self.current_length < self.buf.len() {
-            unsafe { self.push_unchecked(c) };
-            Ok(())
+        self.backend.push(c)
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        self.backend.extend_from_slice(other)
+    }
+
+    /// Writes a 128-bit integer with magnitude `v`, see [`divmod10`]
+    fn serialize_128(&mut self, negative: bool, mut v: u128) -> Result<()> {
+        let mut buf = [0; MAX_I128_LEN];
+        let mut i = buf.len();
+        loop {
+            let (quotient, digit) = divmod10(v);
+            i -= 1;
+            buf[i] = digit + b'0';
+            v = quotient;
+
+            if v == 0 {
+                break;
+            }
+        }
+
+        if negative {
+            i -= 1;
+            buf[i] = b'-';
+        }
+
+        if self.group_digits {
+            self.extend_grouped(&buf[i..])
         } else {
-            Err(Error::BufferFull)
+            self.extend_from_slice(&buf[i..])
+        }
+    }
+
+    /// Writes the integer `number` with a comma between groups of three digits
+    fn extend_grouped(&mut self, number: &[u8]) -> Result<()> {
+        let digits = match number.split_first() {
+            Some((b'-', digits)) => {
+                self.push(b'-')?;
+                digits
+            }
+            _ => number,
+        };
+
+        for (i, digit) in digits.iter().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                self.push(b',')?;
+            }
+            self.push(*digit)?;
         }
+        Ok(())
     }
 
-    unsafe fn push_unchecked(&mut self, c: u8) {
-        self.buf[self.current_length] = c;
-        self.current_length += 1;
+    /// Opens an array or object
+    fn begin_compound(&mut self, open: u8) -> Result<()> {
+        if self.depth >= self.config.max_depth {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        self.depth += 1;
+        self.push(open)
     }
 
-    fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
-        if self.current_length + other.len() > self.buf.len() {
-            // won't fit in the buf; don't modify anything and return an error
-            Err(Error::BufferFull)
+    /// Separates an element or member from the previous one
+    fn begin_element(&mut self, first: bool) -> Result<()> {
+        if !first {
+            self.push(b',')?;
+        }
+        if self.config.pretty {
+            self.push(b'\n')?;
+            self.push_indent()?;
+        }
+        Ok(())
+    }
+
+    /// Separates an object key from its value
+    fn key_separator(&mut self) -> Result<()> {
+        if self.config.pretty {
+            self.extend_from_slice(b": ")
         } else {
-            for c in other {
-                unsafe { self.push_unchecked(*c) };
-            }
-            Ok(())
+            self.push(b':')
+        }
+    }
+
+    /// Closes an array or object, which has no elements if `empty` is set
+    fn end_compound(&mut self, close: u8, empty: bool) -> Result<()> {
+        self.depth -= 1;
+        if self.config.pretty && !empty {
+            self.push(b'\n')?;
+            self.push_indent()?;
+        }
+        self.push(close)
+    }
+
+    fn push_indent(&mut self) -> Result<()> {
+        for _ in 0..self.depth {
+            self.extend_from_slice(self.config.indent.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn serialize_non_finite(&mut self, nan: bool, negative: bool) -> Result<()> {
+        match self.config.non_finite {
+            NonFiniteFloat::Null => self.extend_from_slice(b"null"),
+            NonFiniteFloat::Quoted if nan => self.extend_from_slice(b"\"NaN\""),
+            NonFiniteFloat::Quoted if negative => self.extend_from_slice(b"\"-Infinity\""),
+            NonFiniteFloat::Quoted => self.extend_from_slice(b"\"Infinity\""),
         }
     }
 
@@ -147,6 +390,25 @@ impl<'a> Serializer<'a> {
                 self.push(hex1)?;
                 self.push(hex2)?;
             }
+            '/' if self.config.escape_solidus => {
+                self.push(b'\\')?;
+                self.push(b'/')?;
+            }
+            '\u{0080}'.. if self.config.escape_non_ascii => {
+                let mut utf16 = [0u16; 2];
+                for unit in c.encode_utf16(&mut utf16) {
+                    let [high, low] = unit.to_be_bytes();
+                    let (hex1, hex2) = hex(high);
+                    let (hex3, hex4) = hex(low);
+                    self.extend_from_slice(&[b'\\', b'u', hex1, hex2, hex3, hex4])?;
+                }
+            }
+            '\u{2028}' if self.config.escape_line_separators => {
+                self.extend_from_slice(b"\\u2028")?;
+            }
+            '\u{2029}' if self.config.escape_line_separators => {
+                self.extend_from_slice(b"\\u2029")?;
+            }
             _ => {
                 let encoded = c.encode_utf8(&mut encoding_tmp as &mut [u8]);
                 self.extend_from_slice(encoded.as_bytes())?;
@@ -157,6 +419,27 @@ impl<'a> Serializer<'a> {
     }
 }
 
+/// Maximum length of a serialized `i8`, `-128`
+pub const MAX_I8_LEN: usize = 4;
+/// Maximum length of a serialized `i16`, `-32768`
+pub const MAX_I16_LEN: usize = 6;
+/// Maximum length of a serialized `i32`, `-2147483648`
+pub const MAX_I32_LEN: usize = 11;
+/// Maximum length of a serialized `i64`, `-9223372036854775808`
+pub const MAX_I64_LEN: usize = 20;
+/// Maximum length of a serialized `u8`, `255`
+pub const MAX_U8_LEN: usize = 3;
+/// Maximum length of a serialized `u16`, `65535`
+pub const MAX_U16_LEN: usize = 5;
+/// Maximum length of a serialized `u32`, `4294967295`
+pub const MAX_U32_LEN: usize = 10;
+/// Maximum length of a serialized `u64`, `18446744073709551615`
+pub const MAX_U64_LEN: usize = 20;
+/// Maximum length of a serialized `i128`, `-170141183460469231731687303715884105728`
+pub const MAX_I128_LEN: usize = 40;
+/// Maximum length of a serialized `u128`, `340282366920938463463374607431768211455`
+pub const MAX_U128_LEN: usize = 39;
+
 // NOTE(serialize_*signed) This is basically the numtoa implementation minus the lookup tables,
 // which take 200+ bytes of ROM / Flash
 macro_rules! serialize_unsigned {
@@ -179,7 +462,11 @@ macro_rules! serialize_unsigned {
         // Note(feature): maybe_uninit_slice
         let buf = unsafe { &*(&buf[i..] as *const _ as *const [u8]) };
 
-        $self.extend_from_slice(buf)
+        if $self.group_digits {
+            $self.extend_grouped(buf)
+        } else {
+            $self.extend_from_slice(buf)
+        }
     }};
 }
 
@@ -216,16 +503,29 @@ macro_rules! serialize_signed {
         // Note(feature): maybe_uninit_slice
         let buf = unsafe { &*(&buf[i..] as *const _ as *const [u8]) };
 
-        $self.extend_from_slice(buf)
+        if $self.group_digits {
+            $self.extend_grouped(buf)
+        } else {
+            $self.extend_from_slice(buf)
+        }
     }};
 }
 
-macro_rules! serialize_ryu {
-    ($self:ident, $v:expr) => {{
-        let mut buffer = ryu::Buffer::new();
-        let printed = buffer.format($v);
-        $self.extend_from_slice(printed.as_bytes())
-    }};
+/// Divides `v` by 10, returning the quotient and the remainder
+///
+/// Dividing a `u128` directly pulls in the large `__udivti3` and `__umodti3` intrinsics on 32-bit
+/// targets, so this does long division on 32-bit limbs with 64-bit arithmetic instead, which the
+/// 64-bit integers need anyway.
+fn divmod10(v: u128) -> (u128, u8) {
+    let mut quotient = 0;
+    let mut remainder = 0u64;
+    for shift in [96, 64, 32, 0] {
+        // `remainder < 10`, so the partial quotient fits in 32 bits
+        let partial = (remainder << 32) | u64::from((v >> shift) as u32);
+        quotient |= u128::from(partial / 10) << shift;
+        remainder = partial % 10;
+    }
+    (quotient, remainder as u8)
 }
 
 /// Upper-case hex for value in 0..16, encoded as ASCII bytes
@@ -242,16 +542,16 @@ fn hex(c: u8) -> (u8, u8) {
     (hex_4bit(c >> 4), hex_4bit(c & 0x0F))
 }
 
-impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
+impl<'a, 'b: 'a, B: SerializerBackend> ser::Serializer for &'a mut Serializer<'b, B> {
     type Ok = ();
     type Error = Error;
-    type SerializeSeq = SerializeSeq<'a, 'b>;
-    type SerializeTuple = SerializeSeq<'a, 'b>;
-    type SerializeTupleStruct = SerializeSeq<'a, 'b>;
-    type SerializeTupleVariant = Unreachable;
-    type SerializeMap = SerializeMap<'a, 'b>;
-    type SerializeStruct = SerializeStruct<'a, 'b>;
-    type SerializeStructVariant = SerializeStructVariant<'a, 'b>;
+    type SerializeSeq = SerializeSeq<'a, 'b, B>;
+    type SerializeTuple = SerializeSeq<'a, 'b, B>;
+    type SerializeTupleStruct = SerializeSeq<'a, 'b, B>;
+    type SerializeTupleVariant = SerializeTupleVariant<'a, 'b, B>;
+    type SerializeMap = SerializeMap<'a, 'b, B>;
+    type SerializeStruct = SerializeStruct<'a, 'b, B>;
+    type SerializeStructVariant = SerializeStructVariant<'a, 'b, B>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
         if v {
@@ -263,62 +563,72 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
         // "-128"
-        serialize_signed!(self, 4, v, i8, u8)
+        serialize_signed!(self, MAX_I8_LEN, v, i8, u8)
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
         // "-32768"
-        serialize_signed!(self, 6, v, i16, u16)
+        serialize_signed!(self, MAX_I16_LEN, v, i16, u16)
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
         // "-2147483648"
-        serialize_signed!(self, 11, v, i32, u32)
+        serialize_signed!(self, MAX_I32_LEN, v, i32, u32)
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
         // "-9223372036854775808"
-        serialize_signed!(self, 20, v, i64, u64)
+        serialize_signed!(self, MAX_I64_LEN, v, i64, u64)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok> {
+        // "-170141183460469231731687303715884105728"
+        self.serialize_128(v < 0, v.unsigned_abs())
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
         // "255"
-        serialize_unsigned!(self, 3, v)
+        serialize_unsigned!(self, MAX_U8_LEN, v)
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
         // "65535"
-        serialize_unsigned!(self, 5, v)
+        serialize_unsigned!(self, MAX_U16_LEN, v)
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
         // "4294967295"
-        serialize_unsigned!(self, 10, v)
+        serialize_unsigned!(self, MAX_U32_LEN, v)
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
         // "18446744073709551615"
-        serialize_unsigned!(self, 20, v)
+        serialize_unsigned!(self, MAX_U64_LEN, v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok> {
+        // "340282366920938463463374607431768211455"
+        self.serialize_128(false, v)
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
         if v.is_finite() {
-            serialize_ryu!(self, v)
+            self.number_format.write_f32(v, &mut self.backend)
         } else {
-            self.serialize_none()
+            self.serialize_non_finite(v.is_nan(), v.is_sign_negative())
         }
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
         if v.is_finite() {
-            serialize_ryu!(self, v)
+            self.number_format.write_f64(v, &mut self.backend)
         } else {
-            self.serialize_none()
+            self.serialize_non_finite(v.is_nan(), v.is_sign_negative())
         }
     }
 
-    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
-        unreachable!()
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
@@ -367,13 +677,14 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
     where
         T: ser::Serialize + ?Sized,
     {
-        // If the newtype struct is an `EscapedStr`...
-        if name == crate::str::EscapedStr::NAME {
-            // serialize it as an already escaped string.
+        let escaped = name == crate::str::EscapedStr::NAME;
+        // If the newtype struct is an `EscapedStr` or a `RawValue`...
+        if escaped || name == crate::str::RawValue::NAME {
+            // serialize it as an already escaped string or as JSON text.
 
-            struct EscapedStringSerializer<'a, 'b>(&'a mut Serializer<'b>);
+            struct EscapedStringSerializer<'a, 'b, B>(&'a mut Serializer<'b, B>);
 
-            impl<'a, 'b: 'a> serde::Serializer for EscapedStringSerializer<'a, 'b> {
+            impl<'a, 'b: 'a, B: SerializerBackend> serde::Serializer for EscapedStringSerializer<'a, 'b, B> {
                 type Ok = ();
                 type Error = Error;
 
@@ -434,7 +745,7 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
                 }
 
                 fn serialize_str(self, v: &str) -> Result<Self::Ok> {
-                    v.bytes().try_for_each(|c| self.0.push(c))
+                    self.0.extend_from_slice(v.as_bytes())
                 }
 
                 fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
@@ -537,11 +848,15 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
                 }
             }
 
-            self.push(b'"')?;
+            if escaped {
+                self.push(b'"')?;
+            }
 
-            value.serialize(EscapedStringSerializer(self))?;
+            value.serialize(EscapedStringSerializer(&mut *self))?;
 
-            self.push(b'"')?;
+            if escaped {
+                self.push(b'"')?;
+            }
 
             Ok(())
         } else {
@@ -559,15 +874,17 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
     where
         T: ser::Serialize + ?Sized,
     {
-        self.push(b'{')?;
-        let mut s = SerializeStruct::new(self);
-        s.serialize_field(variant, value)?;
-        s.end()?;
-        Ok(())
+        // Not a struct field, so neither skipped nor masked
+        self.begin_compound(b'{')?;
+        self.begin_element(true)?;
+        self.serialize_str(variant)?;
+        self.key_separator()?;
+        value.serialize(&mut *self)?;
+        self.end_compound(b'}', false)
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        self.push(b'[')?;
+        self.begin_compound(b'[')?;
 
         Ok(SerializeSeq::new(self))
     }
@@ -588,20 +905,26 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        unreachable!()
+        self.begin_compound(b'{')?;
+        self.begin_element(true)?;
+        ser::Serializer::serialize_str(&mut *self, variant)?;
+        self.key_separator()?;
+        self.begin_compound(b'[')?;
+
+        Ok(SerializeTupleVariant::new(self))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        self.push(b'{')?;
+        self.begin_compound(b'{')?;
 
         Ok(SerializeMap::new(self))
     }
 
     fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        self.push(b'{')?;
+        self.begin_compound(b'{')?;
 
         Ok(SerializeStruct::new(self))
     }
@@ -613,47 +936,72 @@ impl<'a, 'b: 'a> ser::Serializer for &'a mut Serializer<'b> {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.extend_from_slice(b"{\"")?;
-        self.extend_from_slice(variant.as_bytes())?;
-        self.extend_from_slice(b"\":{")?;
-
-        Ok(SerializeStructVariant::new(self))
+        Ok(SerializeStructVariant::new(self, variant))
     }
 
     fn collect_str<T>(self, value: &T) -> Result<Self::Ok>
     where
         T: fmt::Display + ?Sized,
     {
-        self.push(b'"')?;
-
-        let mut col = StringCollector::new(self);
-        fmt::write(&mut col, format_args!("{}", value)).or(Err(Error::BufferFull))?;
-
-        self.push(b'"')
+        let mut writer = JsonStringWriter::new(self)?;
+        if fmt::write(&mut writer, format_args!("{}", value)).is_err() {
+            return Err(writer.error.unwrap_or(Error::BufferFull));
+        }
+        writer.finish()
     }
 }
 
-struct StringCollector<'a, 'b> {
-    ser: &'a mut Serializer<'b>,
+/// Writes a JSON string value piece by piece through [`core::fmt::Write`], escaping everything
+/// written to it.
+///
+/// The opening quote is written by [`JsonStringWriter::new`] and the closing one by
+/// [`JsonStringWriter::finish`].
+///
+/// ```
+/// # use core::fmt::Write;
+/// # use serde_json_core::ser::{JsonStringWriter, Serializer};
+/// let mut buf = [0; 32];
+/// let mut ser = Serializer::new(&mut buf);
+/// let mut writer = JsonStringWriter::new(&mut ser).unwrap();
+/// write!(writer, "{}={}", "\"key\"", 42).unwrap();
+/// writer.finish().unwrap();
+///
+/// let len = ser.end();
+/// assert_eq!(&buf[..len], br#""\"key\"=42""#);
+/// ```
+pub struct JsonStringWriter<'a, 'b, B = Slice<'b>> {
+    ser: &'a mut Serializer<'b, B>,
+    error: Option<Error>,
 }
 
-impl<'a, 'b> StringCollector<'a, 'b> {
-    pub fn new(ser: &'a mut Serializer<'b>) -> Self {
-        Self { ser }
+impl<'a, 'b, B: SerializerBackend> JsonStringWriter<'a, 'b, B> {
+    /// Start a string value by writing the opening quote
+    pub fn new(ser: &'a mut Serializer<'b, B>) -> Result<Self> {
+        ser.push(b'"')?;
+        Ok(JsonStringWriter { ser, error: None })
     }
 
-    fn do_write_str(&mut self, s: &str) -> Result<()> {
-        for c in s.chars() {
-            self.ser.push_char(c)?;
+    /// End the string value by writing the closing quote
+    ///
+    /// Returns the error that made an earlier write fail, if any.
+    pub fn finish(self) -> Result<()> {
+        match self.error {
+            Some(error) => Err(error),
+            None => self.ser.push(b'"'),
         }
-
-        Ok(())
     }
 }
 
-impl<'a, 'b> fmt::Write for StringCollector<'a, 'b> {
+impl<'a, 'b, B: SerializerBackend> fmt::Write for JsonStringWriter<'a, 'b, B> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        self.do_write_str(s).or(Err(fmt::Error))
+        for c in s.chars() {
+            if let Err(error) = self.ser.push_char(c) {
+                self.error = Some(error);
+                return Err(fmt::Error);
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -672,11 +1020,97 @@ pub fn to_vec<T, const N: usize>(value: &T) -> Result<Vec<u8, N>>
 where
     T: ser::Serialize + ?Sized,
 {
-    let mut buf = Vec::<u8, N>::new();
-    buf.resize_default(N)?;
-    let len = to_slice(value, &mut buf)?;
-    buf.truncate(len);
-    Ok(buf)
+    let mut ser = Serializer::with_backend(Vec::<u8, N>::new());
+    value.serialize(&mut ser)?;
+    Ok(ser.into_backend())
+}
+
+/// Serializes the given data structure as a string of JSON text, keeping as much of it as fits
+/// instead of failing with `BufferFull`
+///
+/// Returns the string and whether it was truncated. The output is cut at a character boundary,
+/// so a truncated string is generally not valid JSON, e.g. for logging.
+#[cfg(feature = "heapless")]
+pub fn to_string_or_truncate<T, const N: usize>(value: &T) -> Result<(String<N>, bool)>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut vec = Vec::<u8, N>::new();
+    vec.resize_default(N)?;
+
+    let mut ser = Serializer::with_backend(block::Window::new(&mut vec, 0));
+    let truncated = match value.serialize(&mut ser) {
+        Err(Error::BufferFull) => true,
+        result => result.map(|()| false)?,
+    };
+    let len = ser.end();
+    vec.truncate(len);
+
+    let len = match str::from_utf8(&vec) {
+        Ok(_) => len,
+        Err(e) => e.valid_up_to(),
+    };
+    vec.truncate(len);
+
+    Ok((unsafe { String::from_utf8_unchecked(vec) }, truncated))
+}
+
+/// Serializes the given data structure as pretty-printed JSON for debugging, e.g. with `log` or
+/// `defmt`
+///
+/// If the value doesn't fit, or fails to serialize, the output ends with `...` after as much of it
+/// as fits.
+#[cfg(feature = "heapless")]
+pub fn debug_json<T, const N: usize>(value: &T) -> String<N>
+where
+    T: ser::Serialize + ?Sized,
+{
+    const ELLIPSIS: &str = "...";
+
+    let mut vec = Vec::<u8, N>::new();
+    vec.resize_default(N).ok();
+
+    let mut ser = Serializer::with_backend(block::Window::new(&mut vec, 0))
+        .with_config(Config::default().pretty(true));
+    let complete = value.serialize(&mut ser).is_ok();
+    let mut len = ser.end();
+    if !complete {
+        len = len.min(N.saturating_sub(ELLIPSIS.len()));
+    }
+
+    let len = match str::from_utf8(&vec[..len]) {
+        Ok(_) => len,
+        Err(e) => e.valid_up_to(),
+    };
+    vec.truncate(len);
+
+    let mut string = unsafe { String::from_utf8_unchecked(vec) };
+    if !complete {
+        // Only fails if `N` is too small for the ellipsis alone
+        string.push_str(ELLIPSIS).ok();
+    }
+    string
+}
+
+/// Serializes the given data structure as human-readable text for display, with a comma between
+/// groups of three digits of integers, like `1,000,000`
+///
+/// The output looks like JSON but is **not valid JSON** and can't be deserialized again; use
+/// [`to_string`] for that. Floats are written as usual.
+///
+/// ```
+/// let text = serde_json_core::ser::to_display_string::<_, 32>(&[1000000, -1234, 12]).unwrap();
+/// assert_eq!(text, "[1,000,000,-1,234,12]");
+/// ```
+#[cfg(feature = "heapless")]
+pub fn to_display_string<T, const N: usize>(value: &T) -> Result<String<N>>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut ser = Serializer::with_backend(Vec::<u8, N>::new());
+    ser.group_digits = true;
+    value.serialize(&mut ser)?;
+    Ok(unsafe { String::from_utf8_unchecked(ser.into_backend()) })
 }
 
 /// Serializes the given data structure as a JSON byte vector into the provided buffer
@@ -686,7 +1120,107 @@ where
 {
     let mut ser = Serializer::new(buf);
     value.serialize(&mut ser)?;
-    Ok(ser.current_length)
+    Ok(ser.end())
+}
+
+/// Serializes only the fields of the given struct whose keys are in `fields` as a JSON byte
+/// vector into the provided buffer
+///
+/// Nested values are serialized in full, see [`Serializer::with_field_mask`].
+pub fn to_slice_projected<T>(value: &T, buf: &mut [u8], fields: &[&str]) -> Result<usize>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut ser = Serializer::new(buf).with_field_mask(fields);
+    value.serialize(&mut ser)?;
+    Ok(ser.end())
+}
+
+/// Serializes the given data structure as a JSON byte array of capacity `N`
+///
+/// Returns the array along with the length of the JSON data at its start; the rest is zeroed.
+pub fn to_array<T, const N: usize>(value: &T) -> Result<([u8; N], usize)>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut buf = [0; N];
+    let len = to_slice(value, &mut buf)?;
+    Ok((buf, len))
+}
+
+/// Serializes the given data structure as a JSON byte vector into the provided buffer, formatted
+/// according to `config`
+pub fn to_slice_with_config<T>(value: &T, buf: &mut [u8], config: &Config) -> Result<usize>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut ser = Serializer::new(buf).with_config(config.clone());
+    value.serialize(&mut ser)?;
+    Ok(ser.end())
+}
+
+/// Like [`to_slice`], but in debug builds panics if the output isn't valid UTF-8
+///
+/// The `Serializer` itself only produces UTF-8, so this catches custom `Serialize` implementations
+/// passing raw bytes to `serialize_bytes`. In release builds this is the same as `to_slice`.
+pub fn to_slice_checked<T>(value: &T, buf: &mut [u8]) -> Result<usize>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let len = to_slice(value, buf)?;
+    if cfg!(debug_assertions) {
+        if let Err(e) = str::from_utf8(&buf[..len]) {
+            panic!(
+                "serialized JSON is not valid UTF-8 at byte {} of {}; \
+                 check `Serialize` implementations calling `serialize_bytes`",
+                e.valid_up_to(),
+                len
+            );
+        }
+    }
+    Ok(len)
+}
+
+/// Serializes the given data structure as a JSON byte vector into the provided buffer, discarding
+/// the first `skip` bytes of the output
+///
+/// Returns the number of bytes written into `buf`. Together with a deterministic `Serialize`
+/// implementation this allows pulling arbitrary windows out of the serialized data.
+pub fn to_slice_from<T>(value: &T, buf: &mut [u8], skip: usize) -> Result<usize>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let mut ser = Serializer::with_backend(block::Window::new(buf, skip));
+    value.serialize(&mut ser)?;
+    Ok(ser.end())
+}
+
+/// Serializes the given data structure as compact JSON into `f`, e.g. within a `Display` impl
+pub fn to_fmt<T, W>(value: &T, f: &mut W) -> fmt::Result
+where
+    T: ser::Serialize + ?Sized,
+    W: fmt::Write + ?Sized,
+{
+    let mut ser = Serializer::with_backend(FmtWriter::new(f));
+    value.serialize(&mut ser).or(Err(fmt::Error))
+}
+
+/// Displays the wrapped value as compact JSON
+///
+/// ```
+/// # use serde_json_core::ser::Json;
+/// assert_eq!(format!("{}", Json(&[1, 2])), "[1,2]");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Json<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T> fmt::Display for Json<'a, T>
+where
+    T: ser::Serialize + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        to_fmt(self.0, f)
+    }
 }
 
 impl ser::Error for Error {
@@ -694,11 +1228,13 @@ impl ser::Error for Error {
     where
         T: fmt::Display,
     {
-        unreachable!()
+        Error::CustomError
     }
 }
 
-/// An unreachable type to fill the SerializeTupleVariant type
+/// An unreachable type that used to fill the SerializeTupleVariant type
+///
+/// No longer used: tuple variants are supported.
 pub enum Unreachable {}
 
 impl ser::SerializeTupleVariant for Unreachable {
@@ -718,7 +1254,36 @@ impl ser::SerializeTupleVariant for Unreachable {
 mod tests {
     use serde_derive::Serialize;
 
-    const N: usize = 128;
+    use crate::ser::{Config, NumberFormat, Ryu, Serializer};
+
+    const N: usize = 256;
+
+    /// Serializes `value` formatted according to `config`, panicking on errors
+    fn to_string_with<T: serde::Serialize + ?Sized>(
+        value: &T,
+        config: &Config,
+    ) -> heapless::String<N> {
+        to_string_with_format(value, config, &Ryu)
+    }
+
+    /// Like `to_string_with`, writing floats with `format`
+    fn to_string_with_format<T: serde::Serialize + ?Sized>(
+        value: &T,
+        config: &Config,
+        format: &dyn NumberFormat,
+    ) -> heapless::String<N> {
+        let mut buf = [0; N];
+        let mut ser = Serializer::new(&mut buf)
+            .with_config(config.clone())
+            .with_number_format(format);
+        value.serialize(&mut ser).unwrap();
+        let len = ser.end();
+        let mut string = heapless::String::new();
+        string
+            .push_str(str::from_utf8(&buf[..len]).unwrap())
+            .unwrap();
+        string
+    }
 
     #[test]
     fn array() {
@@ -760,6 +1325,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn adjacently_tagged_enum() {
+        #[derive(Serialize)]
+        #[serde(tag = "t", content = "c")]
+        enum Message {
+            Ping,
+            Value(u32),
+            Point { x: i8, y: i8 },
+        }
+
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Message::Ping).unwrap(),
+            r#"{"t":"Ping"}"#
+        );
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Message::Value(7)).unwrap(),
+            r#"{"t":"Value","c":7}"#
+        );
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Message::Point { x: 1, y: -2 }).unwrap(),
+            r#"{"t":"Point","c":{"x":1,"y":-2}}"#
+        );
+    }
+
     #[test]
     fn str() {
         assert_eq!(&*crate::to_string::<_, N>("hello").unwrap(), r#""hello""#);
@@ -831,6 +1420,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn str_line_separators() {
+        use serde::Serialize;
+
+        // Valid JSON as is, so not escaped by default
+        assert_eq!(
+            &*crate::to_string::<_, N>("a\u{2028}b\u{2029}c").unwrap(),
+            "\"a\u{2028}b\u{2029}c\""
+        );
+
+        let buf = &mut [0u8; 128];
+        let mut ser = crate::ser::Serializer::new(buf).escape_line_separators(true);
+        "a\u{2028}b\u{2029}c ä ℝ 💣".serialize(&mut ser).unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], r#""a\u2028b\u2029c ä ℝ 💣""#.as_bytes());
+    }
+
     #[test]
     fn escaped_str() {
         assert_eq!(
@@ -926,56 +1532,965 @@ mod tests {
     }
 
     #[test]
-    fn struct_option() {
-        #[derive(Serialize)]
-        struct Property<'a> {
-            description: Option<&'a str>,
+    fn integers_128() {
+        use crate::ser::{MAX_I128_LEN, MAX_U128_LEN};
+        use core::fmt::{Display, Write};
+
+        fn check<T: serde::Serialize + Display>(v: T) {
+            let mut reference = heapless::String::<64>::new();
+            write!(reference, "{}", v).unwrap();
+            assert_eq!(crate::to_string::<_, 64>(&v).unwrap(), reference);
+        }
+
+        for v in [
+            0,
+            1,
+            9,
+            10,
+            u128::from(u64::MAX),
+            u128::from(u64::MAX) + 1,
+            u128::from(u32::MAX) << 32,
+            10u128.pow(38),
+            10u128.pow(38) - 1,
+            0x0123_4567_89ab_cdef_fedc_ba98_7654_3210,
+            u128::MAX - 1,
+            u128::MAX,
+        ] {
+            check(v);
+        }
+        for v in [
+            0,
+            -1,
+            1,
+            i128::from(i64::MIN) - 1,
+            i128::MIN + 1,
+            i128::MIN,
+            i128::MAX,
+        ] {
+            check(v);
+        }
+
+        // Every power of ten and its neighbours
+        let mut power = 1u128;
+        while let Some(next) = power.checked_mul(10) {
+            check(power - 1);
+            check(power);
+            check(power + 1);
+            power = next;
         }
 
         assert_eq!(
-            crate::to_string::<_, N>(&Property {
-                description: Some("An ambient temperature sensor"),
-            })
-            .unwrap(),
-            r#"{"description":"An ambient temperature sensor"}"#
+            crate::ser::to_display_string::<_, N>(&-1_000_000i128).unwrap(),
+            "-1,000,000"
         );
-
-        // XXX Ideally this should produce "{}"
         assert_eq!(
-            crate::to_string::<_, N>(&Property { description: None }).unwrap(),
-            r#"{"description":null}"#
+            crate::to_slice(&i128::MIN, &mut [0; MAX_I128_LEN]),
+            Ok(MAX_I128_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&u128::MAX, &mut [0; MAX_U128_LEN - 1]),
+            Err(crate::ser::Error::BufferFull)
         );
     }
 
     #[test]
-    fn struct_u8() {
-        #[derive(Serialize)]
-        struct Temperature {
-            temperature: u8,
-        }
+    fn max_integer_lengths() {
+        use crate::ser::*;
+
+        const _: () = assert!(MAX_I8_LEN == "-128".len());
+        const _: () = assert!(MAX_I16_LEN == "-32768".len());
+        const _: () = assert!(MAX_I32_LEN == "-2147483648".len());
+        const _: () = assert!(MAX_I64_LEN == "-9223372036854775808".len());
+        const _: () = assert!(MAX_U8_LEN == "255".len());
+        const _: () = assert!(MAX_U16_LEN == "65535".len());
+        const _: () = assert!(MAX_U32_LEN == "4294967295".len());
+        const _: () = assert!(MAX_U64_LEN == "18446744073709551615".len());
+        const _: () = assert!(MAX_I128_LEN == "-170141183460469231731687303715884105728".len());
+        const _: () = assert!(MAX_U128_LEN == "340282366920938463463374607431768211455".len());
 
         assert_eq!(
-            &*crate::to_string::<_, N>(&Temperature { temperature: 20 }).unwrap(),
-            r#"{"temperature":20}"#
+            crate::to_slice(&i8::MIN, &mut [0; MAX_I8_LEN]),
+            Ok(MAX_I8_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&i16::MIN, &mut [0; MAX_I16_LEN]),
+            Ok(MAX_I16_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&i32::MIN, &mut [0; MAX_I32_LEN]),
+            Ok(MAX_I32_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&i64::MIN, &mut [0; MAX_I64_LEN]),
+            Ok(MAX_I64_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&u8::MAX, &mut [0; MAX_U8_LEN]),
+            Ok(MAX_U8_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&u16::MAX, &mut [0; MAX_U16_LEN]),
+            Ok(MAX_U16_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&u32::MAX, &mut [0; MAX_U32_LEN]),
+            Ok(MAX_U32_LEN)
+        );
+        assert_eq!(
+            crate::to_slice(&u64::MAX, &mut [0; MAX_U64_LEN]),
+            Ok(MAX_U64_LEN)
         );
     }
 
     #[test]
-    fn struct_() {
-        #[derive(Serialize)]
+    fn negative_zero() {
+        assert_eq!(&*crate::to_string::<_, N>(&-0.0f32).unwrap(), "-0.0");
+        assert_eq!(&*crate::to_string::<_, N>(&-0.0f64).unwrap(), "-0.0");
+        assert_eq!(&*crate::to_string::<_, N>(&0.0f32).unwrap(), "0.0");
+
+        let buf = &mut [0u8; N];
+        let config = crate::ser::Config::default().non_finite(crate::ser::NonFiniteFloat::Quoted);
+        let len = crate::ser::to_slice_with_config(&-0.0f32, buf, &config).unwrap();
+        assert_eq!(&buf[..len], b"-0.0");
+    }
+
+    #[test]
+    fn number_format() {
+        use crate::ser::{Error, Fixed, NumberFormat, Ryu, Serializer, Trimmed};
+        use serde::Serialize;
+
+        fn to_string(value: &impl Serialize, format: &dyn NumberFormat) -> heapless::String<N> {
+            to_string_with_format(value, &Config::default(), format)
+        }
+
+        let values = (1.5f32, -0.25f64, 100.0f64, f32::NAN);
+        assert_eq!(to_string(&values, &Ryu), "[1.5,-0.25,100.0,null]");
+        assert_eq!(to_string(&values, &Fixed(3)), "[1.500,-0.250,100.000,null]");
+        assert_eq!(to_string(&values, &Fixed(0)), "[2,-0,100,null]");
+        assert_eq!(to_string(&1.126f64, &Fixed(2)), "1.13");
+
+        let values = (2.5f32, 2.0f64, -0.125f64, 1.0005f64, 0.0f32);
+        assert_eq!(to_string(&values, &Trimmed(3)), "[2.5,2,-0.125,1,0]");
+        let values = (-0.0001f32, -0.0f64, 0.0506f64, 100.0f32, -12.3456f64);
+        assert_eq!(to_string(&values, &Trimmed(3)), "[0,0,0.051,100,-12.346]");
+        assert_eq!(to_string(&(-0.4f32, 20.7f64), &Trimmed(0)), "[0,21]");
+
+        // A number that doesn't fit is reported as such
+        let mut buf = [0; 4];
+        let mut ser = Serializer::new(&mut buf).with_number_format(&Fixed(4));
+        assert_eq!(1.0f32.serialize(&mut ser), Err(Error::BufferFull));
+    }
+
+    #[test]
+    fn to_array() {
+        let value = (1u8, "two", [3.0f32]);
+
+        let (array, len) = crate::ser::to_array::<_, 32>(&value).unwrap();
+        let mut buf = [0; 32];
+        let slice_len = crate::to_slice(&value, &mut buf).unwrap();
+        assert_eq!(&array[..len], &buf[..slice_len]);
+        assert_eq!(&array[..len], br#"[1,"two",[3.0]]"#);
+        assert!(array[len..].iter().all(|&b| b == 0));
+
+        assert_eq!(
+            crate::ser::to_array::<_, 8>(&value),
+            Err(crate::ser::Error::BufferFull)
+        );
+    }
+
+    #[test]
+    fn hashing() {
+        use crate::ser::{Hashing, Serializer, SerializerBackend, Slice, Tee};
+        use core::hash::Hasher;
+        use serde::Serialize;
+
+        /// 32-bit FNV-1a, which hashes the same data written in pieces or all at once alike
+        struct Fnv(u32);
+
+        impl Hasher for Fnv {
+            fn write(&mut self, bytes: &[u8]) {
+                for b in bytes {
+                    self.0 = (self.0 ^ u32::from(*b)).wrapping_mul(0x0100_0193);
+                }
+            }
+
+            fn finish(&self) -> u64 {
+                self.0.into()
+            }
+        }
+
+        let value = (1u8, "two", [3.5f32]);
+
+        let mut buf = [0; N];
+        let len = crate::to_slice(&value, &mut buf).unwrap();
+        let mut expected = Fnv(0x811c_9dc5);
+        expected.write(&buf[..len]);
+
+        let mut ser = Serializer::with_backend(Hashing::new(Fnv(0x811c_9dc5)));
+        value.serialize(&mut ser).unwrap();
+        assert_eq!(ser.end(), len);
+        assert_eq!(ser.into_backend().hasher().finish(), expected.finish());
+
+        // Hash while writing the output
+        let mut out = [0; N];
+        let mut ser = Serializer::with_backend(Tee::new(
+            Slice::new(&mut out),
+            Hashing::new(Fnv(0x811c_9dc5)),
+        ));
+        value.serialize(&mut ser).unwrap();
+        let (slice, hashing) = ser.into_backend().into_inner();
+        assert_eq!(slice.end(), len);
+        assert_eq!(hashing.into_inner().finish(), expected.finish());
+        assert_eq!(out[..len], buf[..len]);
+    }
+
+    #[test]
+    fn tee() {
+        use crate::ser::{Counter, Serializer, SerializerBackend, Slice, Tee};
+        use serde::Serialize;
+
+        let value = (1u8, "two", [3.5f32]);
+
+        let mut buf = [0; N];
+        let mut ser = Serializer::with_backend(Tee::new(Slice::new(&mut buf), Counter::new()));
+        value.serialize(&mut ser).unwrap();
+        let len = ser.end();
+        let (_, counter) = ser.into_backend().into_inner();
+        assert_eq!(counter.end(), len);
+        assert_eq!(&buf[..len], br#"[1,"two",[3.5]]"#);
+
+        // Both see the same data
+        let mut copy = heapless::Vec::<u8, N>::new();
+        let mut ser = Serializer::with_backend(Tee::new(Slice::new(&mut buf), &mut copy));
+        value.serialize(&mut ser).unwrap();
+        assert_eq!(&copy[..], &buf[..len]);
+
+        // A full first backend stops the output
+        let mut ser = Serializer::with_backend(Tee::new(Slice::new(&mut buf[..4]), Counter::new()));
+        assert_eq!(
+            value.serialize(&mut ser),
+            Err(crate::ser::Error::BufferFull)
+        );
+
+        // A full second backend leaves the first one untouched
+        let mut small = [0; 4];
+        let mut tee = Tee::new(Slice::new(&mut buf), Slice::new(&mut small));
+        tee.extend_from_slice(b"[1,").unwrap();
+        assert_eq!(
+            tee.extend_from_slice(b"\"two\""),
+            Err(crate::ser::Error::BufferFull)
+        );
+        tee.push(b'2').unwrap();
+        assert_eq!(tee.push(b','), Err(crate::ser::Error::BufferFull));
+        assert_eq!(tee.end(), 4);
+        let (first, second) = tee.into_inner();
+        assert_eq!(first.end(), second.end());
+        assert_eq!(&buf[..4], b"[1,2");
+    }
+
+    #[test]
+    fn erased() {
+        use crate::ser::Serializer;
+
+        let values: [&dyn erased_serde::Serialize; 3] = [&1u8, &"two", &[3.5f32]];
+        let mut buf = [0; N];
+        let len = crate::to_slice(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..len], br#"[1,"two",[3.5]]"#);
+
+        let value: &dyn erased_serde::Serialize = &values;
+        let mut ser = Serializer::new(&mut buf);
+        erased_serde::serialize(value, &mut ser).unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], br#"[1,"two",[3.5]]"#);
+
+        // Errors come back unchanged
+        assert_eq!(
+            erased_serde::serialize(value, &mut Serializer::new(&mut buf[..4])),
+            Err(crate::ser::Error::BufferFull)
+        );
+    }
+
+    #[test]
+    fn projected() {
+        #[derive(Serialize)]
+        struct Inner {
+            a: u8,
+            b: u8,
+        }
+
+        #[derive(Serialize)]
+        struct Outer {
+            a: u8,
+            b: Inner,
+            c: &'static str,
+        }
+
+        let value = Outer {
+            a: 1,
+            b: Inner { a: 2, b: 3 },
+            c: "x",
+        };
+        let mut buf = [0u8; 64];
+
+        let len = crate::ser::to_slice_projected(&value, &mut buf, &["a", "c"]).unwrap();
+        assert_eq!(&buf[..len], br#"{"a":1,"c":"x"}"#);
+
+        let len = crate::ser::to_slice_projected(&value, &mut buf, &["c"]).unwrap();
+        assert_eq!(&buf[..len], br#"{"c":"x"}"#);
+
+        // Nested structs are written in full
+        let len = crate::ser::to_slice_projected(&value, &mut buf, &["b"]).unwrap();
+        assert_eq!(&buf[..len], br#"{"b":{"a":2,"b":3}}"#);
+
+        let len = crate::ser::to_slice_projected(&value, &mut buf, &[]).unwrap();
+        assert_eq!(&buf[..len], b"{}");
+
+        let len = crate::ser::to_slice_projected(&[&value], &mut buf, &["a"]).unwrap();
+        assert_eq!(&buf[..len], br#"[{"a":1,"b":{"a":2,"b":3},"c":"x"}]"#);
+
+        // Only struct fields are masked, not the object wrapping a newtype variant
+        #[derive(Serialize)]
+        enum Choice {
+            A(Option<u8>),
+            B(Inner),
+        }
+
+        let len = crate::ser::to_slice_projected(&Choice::A(Some(1)), &mut buf, &["a"]).unwrap();
+        assert_eq!(&buf[..len], br#"{"A":1}"#);
+        let len =
+            crate::ser::to_slice_projected(&Choice::B(Inner { a: 2, b: 3 }), &mut buf, &["a"])
+                .unwrap();
+        assert_eq!(&buf[..len], br#"{"B":{"a":2,"b":3}}"#);
+
+        let fields = ["c", "a"];
+        let mut ser = crate::ser::Serializer::new(&mut buf)
+            .with_config(crate::ser::Config::default().pretty(true))
+            .with_field_mask(&fields);
+        serde::Serialize::serialize(&value, &mut ser).unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], b"{\n  \"a\": 1,\n  \"c\": \"x\"\n}");
+    }
+
+    #[test]
+    fn collect() {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        struct Collected;
+
+        impl serde::Serialize for Collected {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_seq((1..4).map(|i| (i, [(i * 2, "x")])))
+            }
+        }
+
+        struct Manual;
+
+        impl serde::Serialize for Manual {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut seq = serializer.serialize_seq(Some(3))?;
+                for i in 1..4 {
+                    seq.serialize_element(&(i, ManualMap(i * 2)))?;
+                }
+                seq.end()
+            }
+        }
+
+        struct ManualMap(i32);
+
+        impl serde::Serialize for ManualMap {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(&self.0, "x")?;
+                map.end()
+            }
+        }
+
+        // Iterators don't implement `Serialize`, so `collect_map` is reached through a
+        // wrapper on each element
+        struct CollectedMap<I>(core::cell::Cell<Option<I>>);
+
+        impl<I> serde::Serialize for CollectedMap<I>
+        where
+            I: Iterator<Item = (i32, &'static str)>,
+        {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_map(self.0.take().unwrap())
+            }
+        }
+
+        struct CollectedNested;
+
+        impl serde::Serialize for CollectedNested {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_seq((1..4).map(|i| {
+                    (
+                        i,
+                        CollectedMap(core::cell::Cell::new(Some(core::iter::once((i * 2, "x"))))),
+                    )
+                }))
+            }
+        }
+
+        let expected = r#"[[1,{"2":"x"}],[2,{"4":"x"}],[3,{"6":"x"}]]"#;
+        assert_eq!(&*crate::to_string::<_, 64>(&Manual).unwrap(), expected);
+        assert_eq!(
+            &*crate::to_string::<_, 64>(&CollectedNested).unwrap(),
+            expected
+        );
+
+        // Plain sequences of tuples
+        assert_eq!(
+            &*crate::to_string::<_, 64>(&Collected).unwrap(),
+            &*crate::to_string::<_, 64>(&[(1, [(2, "x")]), (2, [(4, "x")]), (3, [(6, "x")])])
+                .unwrap()
+        );
+
+        // Empty iterators
+        struct Empty;
+
+        impl serde::Serialize for Empty {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_seq(core::iter::empty::<u8>())
+            }
+        }
+
+        assert_eq!(&*crate::to_string::<_, 8>(&Empty).unwrap(), "[]");
+        assert_eq!(
+            &*crate::to_string::<_, 8>(&CollectedMap(core::cell::Cell::new(Some(
+                core::iter::empty()
+            ))))
+            .unwrap(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn array_builder() {
+        use crate::ser::Serializer;
+
+        let mut buf = [0u8; 32];
+        let mut ser = Serializer::new(&mut buf);
+        let mut array = ser.begin_array().unwrap();
+        for i in 0..4u32 {
+            array.push_element(&(i * 3)).unwrap();
+        }
+        array.push_element("end").unwrap();
+        array.finish().unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], br#"[0,3,6,9,"end"]"#);
+
+        let mut ser = Serializer::new(&mut buf);
+        ser.begin_array().unwrap().finish().unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], b"[]");
+
+        let mut buf = [0u8; 4];
+        let mut ser = Serializer::new(&mut buf);
+        let mut array = ser.begin_array().unwrap();
+        array.push_element(&12).unwrap();
+        assert_eq!(array.push_element(&34), Err(crate::ser::Error::BufferFull));
+    }
+
+    #[test]
+    fn object_builder() {
+        use crate::ser::{Config, Serializer};
+
+        let mut buf = [0u8; 32];
+        let mut ser = Serializer::new(&mut buf);
+        let mut object = ser.begin_object().unwrap();
+        object.field("a", &1).unwrap();
+        object.field("b", &true).unwrap();
+        object.finish().unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], br#"{"a":1,"b":true}"#);
+
+        let mut ser = Serializer::new(&mut buf);
+        let mut object = ser.begin_object().unwrap();
+        object.field("say \"hi\"", &[1, 2]).unwrap();
+        object.finish().unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], br#"{"say \"hi\"":[1,2]}"#);
+
+        let mut ser = Serializer::new(&mut buf).with_config(Config::default().pretty(true));
+        ser.begin_object().unwrap().finish().unwrap();
+        let len = ser.end();
+        assert_eq!(&buf[..len], b"{}");
+    }
+
+    #[test]
+    fn remaining() {
+        use crate::ser::{FmtWriter, Serializer, Tee};
+        use serde::Serialize;
+
+        let mut buf = [0u8; 16];
+        let mut ser = Serializer::new(&mut buf);
+        assert_eq!(ser.remaining(), 16);
+        [1, 2].serialize(&mut ser).unwrap();
+        assert_eq!(ser.remaining(), 11);
+        "abc".serialize(&mut ser).unwrap();
+        assert_eq!(ser.remaining(), 6);
+        assert_eq!(ser.end() + ser.remaining(), 16);
+
+        let mut ser = Serializer::with_backend(heapless::Vec::<u8, 8>::new());
+        true.serialize(&mut ser).unwrap();
+        assert_eq!(ser.remaining(), 4);
+
+        let mut buf = [0u8; 16];
+        let mut ser = Serializer::with_backend(Tee::new(
+            heapless::Vec::<u8, 8>::new(),
+            crate::ser::Slice::new(&mut buf),
+        ));
+        1u8.serialize(&mut ser).unwrap();
+        assert_eq!(ser.remaining(), 7);
+
+        let mut string = heapless::String::<8>::new();
+        let mut ser = Serializer::with_backend(FmtWriter::new(&mut string));
+        1u8.serialize(&mut ser).unwrap();
+        assert_eq!(ser.remaining(), usize::MAX);
+    }
+
+    #[test]
+    fn display_string() {
+        use crate::ser::to_display_string;
+
+        #[derive(Serialize)]
+        struct Dashboard {
+            visitors: u64,
+            change: i32,
+            min: i64,
+            ratio: f32,
+        }
+
+        assert_eq!(to_display_string::<_, N>(&1000000).unwrap(), "1,000,000");
+        assert_eq!(to_display_string::<_, N>(&999u16).unwrap(), "999");
+        assert_eq!(to_display_string::<_, N>(&-1000i16).unwrap(), "-1,000");
+        assert_eq!(to_display_string::<_, N>(&0u8).unwrap(), "0");
+        assert_eq!(
+            to_display_string::<_, N>(&u64::MAX).unwrap(),
+            "18,446,744,073,709,551,615"
+        );
+        assert_eq!(
+            to_display_string::<_, N>(&Dashboard {
+                visitors: 12345,
+                change: -100,
+                min: i64::MIN,
+                ratio: 1000.5,
+            })
+            .unwrap(),
+            r#"{"visitors":12,345,"change":-100,"min":-9,223,372,036,854,775,808,"ratio":1000.5}"#
+        );
+
+        // Valid JSON output is unaffected
+        assert_eq!(&*crate::to_string::<_, N>(&1000000).unwrap(), "1000000");
+    }
+
+    #[test]
+    fn json_display() {
+        use crate::ser::Json;
+        use core::fmt::{self, Write};
+
+        #[derive(Serialize)]
+        struct Led {
+            led: bool,
+            brightness: u8,
+        }
+
+        impl fmt::Display for Led {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                crate::ser::to_fmt(self, f)
+            }
+        }
+
+        let led = Led {
+            led: true,
+            brightness: 7,
+        };
+
+        let mut string = heapless::String::<N>::new();
+        write!(string, "{} {}", led, Json(&[Some("a"), None])).unwrap();
+        assert_eq!(string, r#"{"led":true,"brightness":7} ["a",null]"#);
+
+        // The writer running out of space is a formatting error
+        let mut string = heapless::String::<8>::new();
+        assert_eq!(write!(string, "{}", Json(&led)), Err(fmt::Error));
+    }
+
+    #[test]
+    fn skip_unit() {
+        use crate::ser::Config;
+        use core::marker::PhantomData;
+
+        #[derive(Serialize)]
+        struct Unit;
+
+        #[derive(Serialize)]
+        struct Reading {
+            marker: PhantomData<u32>,
+            value: u8,
+            unit: Unit,
+            nothing: (),
+            missing: Option<u8>,
+        }
+
+        let reading = Reading {
+            marker: PhantomData,
+            value: 3,
+            unit: Unit,
+            nothing: (),
+            missing: None,
+        };
+
+        let to_string = |config: &Config| to_string_with(&reading, config);
+
+        assert_eq!(
+            to_string(&Config::default()),
+            r#"{"marker":null,"value":3,"unit":null,"nothing":null,"missing":null}"#
+        );
+        assert_eq!(
+            to_string(&Config::default().skip_unit(true)),
+            r#"{"value":3,"missing":null}"#
+        );
+        assert_eq!(
+            to_string(&Config::default().skip_unit(true).skip_none(true)),
+            r#"{"value":3}"#
+        );
+        assert_eq!(
+            to_string(&Config::default().skip_none(true)),
+            r#"{"marker":null,"value":3,"unit":null,"nothing":null}"#
+        );
+
+        // The value of a newtype variant isn't a struct field, so it's kept
+        #[derive(Serialize)]
+        enum Marker {
+            Empty(()),
+            Typed(PhantomData<u32>),
+        }
+
+        let config = Config::default().skip_unit(true);
+        assert_eq!(
+            to_string_with(&Marker::Empty(()), &config),
+            r#"{"Empty":null}"#
+        );
+        assert_eq!(
+            to_string_with(&Marker::Typed(PhantomData), &config),
+            r#"{"Typed":null}"#
+        );
+    }
+
+    #[test]
+    fn empty_option_sequence() {
+        use crate::ser::Config;
+        use serde_derive::Deserialize;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Readings {
+            fixed: Option<[u8; 0]>,
+            list: Option<heapless::Vec<u8, 4>>,
+        }
+
+        let empty = Readings {
+            fixed: Some([]),
+            list: Some(heapless::Vec::new()),
+        };
+        let missing = Readings {
+            fixed: None,
+            list: None,
+        };
+
+        let json = to_string_with(&empty, &Config::default());
+        assert_eq!(json, r#"{"fixed":[],"list":[]}"#);
+        assert_eq!(crate::from_str(&json), Ok((empty, json.len())));
+        assert_eq!(
+            to_string_with(
+                &Readings {
+                    fixed: Some([]),
+                    list: Some(heapless::Vec::new()),
+                },
+                &Config::default().skip_none(true)
+            ),
+            r#"{"fixed":[],"list":[]}"#
+        );
+
+        let json = to_string_with(&missing, &Config::default());
+        assert_eq!(json, r#"{"fixed":null,"list":null}"#);
+        assert_eq!(crate::from_str(&json), Ok((missing, json.len())));
+        assert_eq!(
+            to_string_with(
+                &Readings {
+                    fixed: None,
+                    list: None,
+                },
+                &Config::default().skip_none(true)
+            ),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn struct_skipped_fields() {
+        #[derive(Serialize)]
+        struct Skipped {
+            #[serde(skip)]
+            _hidden: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Mixed {
+            #[serde(skip)]
+            _hidden: u32,
+            shown: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            maybe: Option<u32>,
+        }
+
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Skipped { _hidden: 1 }).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Mixed {
+                _hidden: 1,
+                shown: 2,
+                maybe: None
+            })
+            .unwrap(),
+            r#"{"shown":2}"#
+        );
+
+        let buf = &mut [0u8; N];
+        let config = crate::ser::Config::default().pretty(true);
+        let len = crate::ser::to_slice_with_config(&Skipped { _hidden: 1 }, buf, &config).unwrap();
+        assert_eq!(&buf[..len], b"{}");
+    }
+
+    #[test]
+    fn sort_struct_fields() {
+        use crate::ser::{Config, Error, FmtWriter, Serializer};
+        use serde::Serialize;
+
+        #[derive(Serialize)]
+        struct Inner {
+            zeta: bool,
+            alpha: Option<u8>,
+        }
+
+        #[derive(Serialize)]
+        enum Command {
+            Move { y: i8, x: i8 },
+        }
+
+        #[derive(Serialize)]
+        struct Message<'a> {
+            value: f32,
+            id: u32,
+            ab: &'a str,
+            a: [Inner; 2],
+            b: Command,
+        }
+
+        let message = Message {
+            value: 1.5,
+            id: 7,
+            ab: "x,y\"",
+            a: [
+                Inner {
+                    zeta: true,
+                    alpha: None,
+                },
+                Inner {
+                    zeta: false,
+                    alpha: Some(1),
+                },
+            ],
+            b: Command::Move { y: 2, x: -1 },
+        };
+
+        let to_string = |config: &Config| to_string_with(&message, config);
+
+        let config = Config::default().sort_struct_fields(true);
+        assert_eq!(
+            to_string(&config),
+            r#"{"a":[{"alpha":null,"zeta":true},{"alpha":1,"zeta":false}],"ab":"x,y\"","b":{"Move":{"x":-1,"y":2}},"id":7,"value":1.5}"#
+        );
+
+        assert_eq!(
+            to_string(&config.clone().skip_none(true)),
+            r#"{"a":[{"zeta":true},{"alpha":1,"zeta":false}],"ab":"x,y\"","b":{"Move":{"x":-1,"y":2}},"id":7,"value":1.5}"#
+        );
+
+        let pretty = to_string(&config.clone().pretty(true));
+        assert!(pretty.starts_with(
+            "{\n  \"a\": [\n    {\n      \"alpha\": null,\n      \"zeta\": true\n    },"
+        ));
+        assert!(pretty.ends_with("\"id\": 7,\n  \"value\": 1.5\n}"));
+
+        assert_eq!(
+            &*crate::to_string::<_, N>(&message.b).unwrap(),
+            r#"{"Move":{"y":2,"x":-1}}"#
+        );
+
+        // The output can't be rearranged without access to it
+        let mut string = heapless::String::<256>::new();
+        let mut ser = Serializer::with_backend(FmtWriter::new(&mut string)).with_config(config);
+        assert_eq!(message.serialize(&mut ser), Err(Error::SortingUnsupported));
+    }
+
+    #[test]
+    fn struct_option() {
+        #[derive(Serialize)]
+        struct Property<'a> {
+            description: Option<&'a str>,
+        }
+
+        assert_eq!(
+            crate::to_string::<_, N>(&Property {
+                description: Some("An ambient temperature sensor"),
+            })
+            .unwrap(),
+            r#"{"description":"An ambient temperature sensor"}"#
+        );
+
+        // XXX Ideally this should produce "{}"
+        assert_eq!(
+            crate::to_string::<_, N>(&Property { description: None }).unwrap(),
+            r#"{"description":null}"#
+        );
+    }
+
+    #[test]
+    fn struct_u8() {
+        #[derive(Serialize)]
+        struct Temperature {
+            temperature: u8,
+        }
+
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Temperature { temperature: 20 }).unwrap(),
+            r#"{"temperature":20}"#
+        );
+    }
+
+    #[test]
+    fn struct_() {
+        #[derive(Serialize)]
         struct Empty {}
 
-        assert_eq!(&*crate::to_string::<_, N>(&Empty {}).unwrap(), r#"{}"#);
+        assert_eq!(&*crate::to_string::<_, N>(&Empty {}).unwrap(), r#"{}"#);
+
+        #[derive(Serialize)]
+        struct Tuple {
+            a: bool,
+            b: bool,
+        }
+
+        assert_eq!(
+            &*crate::to_string::<_, N>(&Tuple { a: true, b: false }).unwrap(),
+            r#"{"a":true,"b":false}"#
+        );
+    }
+
+    #[test]
+    fn struct_escaped_key() {
+        use serde_derive::Deserialize;
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Weird {
+            #[serde(rename = "weird\"key")]
+            quote: u8,
+            #[serde(rename = "back\\slash")]
+            backslash: u8,
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        enum Variant {
+            #[serde(rename = "new\nline")]
+            A { x: u8 },
+        }
+
+        let weird = Weird {
+            quote: 1,
+            backslash: 2,
+        };
+        let json = crate::to_string::<_, N>(&weird).unwrap();
+        assert_eq!(json, r#"{"weird\"key":1,"back\\slash":2}"#);
+        assert_eq!(
+            crate::from_str_escaped::<Weird>(&json, &mut [0; 16]),
+            Ok((weird, json.len()))
+        );
+
+        let variant = Variant::A { x: 3 };
+        let json = crate::to_string::<_, N>(&variant).unwrap();
+        assert_eq!(json, r#"{"new\nline":{"x":3}}"#);
+    }
+
+    #[test]
+    fn map_keys() {
+        use heapless::FnvIndexMap;
+
+        #[derive(Debug, Serialize, PartialEq, Eq, Hash)]
+        enum Key {
+            #[serde(rename = "plain")]
+            Plain,
+            #[serde(rename = "quoted \"key\"")]
+            Quoted,
+        }
+
+        let mut map = FnvIndexMap::<_, _, 4>::new();
+        map.insert("new\nline", 1).unwrap();
+        map.insert("plain", 2).unwrap();
+        assert_eq!(
+            crate::to_string::<_, N>(&map).unwrap(),
+            r#"{"new\nline":1,"plain":2}"#
+        );
+
+        let mut map = FnvIndexMap::<_, _, 4>::new();
+        map.insert(Key::Plain, true).unwrap();
+        map.insert(Key::Quoted, false).unwrap();
+        assert_eq!(
+            crate::to_string::<_, N>(&map).unwrap(),
+            r#"{"plain":true,"quoted \"key\"":false}"#
+        );
 
-        #[derive(Serialize)]
-        struct Tuple {
-            a: bool,
-            b: bool,
-        }
+        // Keys which aren't strings are quoted
+        let mut map = FnvIndexMap::<_, _, 4>::new();
+        map.insert(5u32, true).unwrap();
+        map.insert(7u32, false).unwrap();
+        assert_eq!(
+            crate::to_string::<_, N>(&map).unwrap(),
+            r#"{"5":true,"7":false}"#
+        );
 
+        let mut map = FnvIndexMap::<_, _, 4>::new();
+        map.insert(-1i8, "x").unwrap();
+        map.insert(1i8, "\"").unwrap();
         assert_eq!(
-            &*crate::to_string::<_, N>(&Tuple { a: true, b: false }).unwrap(),
-            r#"{"a":true,"b":false}"#
+            crate::to_string::<_, N>(&map).unwrap(),
+            r#"{"-1":"x","1":"\""}"#
+        );
+
+        let mut map = FnvIndexMap::<_, _, 4>::new();
+        map.insert((1, 2), 3).unwrap();
+        assert_eq!(
+            crate::to_string::<_, N>(&map),
+            Err(crate::ser::Error::KeyMustBeAString)
         );
     }
 
@@ -993,6 +2508,43 @@ mod tests {
         assert_eq!(&*crate::to_string::<_, N>(&a).unwrap(), r#"54"#);
     }
 
+    #[test]
+    fn transparent() {
+        use serde_derive::Deserialize;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(transparent)]
+        struct Id(u32);
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(transparent)]
+        struct Name<'a> {
+            name: &'a str,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct User<'a> {
+            id: Id,
+            #[serde(borrow)]
+            name: Name<'a>,
+        }
+
+        assert_eq!(&*crate::to_string::<_, N>(&Id(54)).unwrap(), "54");
+        assert_eq!(crate::from_str::<Id>("54"), Ok((Id(54), 2)));
+
+        let name = Name { name: "ferris" };
+        assert_eq!(&*crate::to_string::<_, N>(&name).unwrap(), r#""ferris""#);
+        assert_eq!(crate::from_str::<Name<'_>>(r#""ferris""#), Ok((name, 8)));
+
+        let user = User {
+            id: Id(7),
+            name: Name { name: "ferris" },
+        };
+        let json = crate::to_string::<_, N>(&user).unwrap();
+        assert_eq!(&*json, r#"{"id":7,"name":"ferris"}"#);
+        assert_eq!(crate::from_str::<User<'_>>(&json), Ok((user, json.len())));
+    }
+
     #[test]
     fn test_newtype_variant() {
         #[derive(Serialize)]
@@ -1004,6 +2556,31 @@ mod tests {
         assert_eq!(&*crate::to_string::<_, N>(&a).unwrap(), r#"{"A":54}"#);
     }
 
+    #[test]
+    fn test_tuple_variant() {
+        use crate::ser::Config;
+
+        #[derive(Serialize)]
+        enum A {
+            A(u32, char),
+            B(),
+        }
+
+        assert_eq!(
+            &*crate::to_string::<_, N>(&A::A(54, '"')).unwrap(),
+            r#"{"A":[54,"\""]}"#
+        );
+        assert_eq!(&*crate::to_string::<_, N>(&A::B()).unwrap(), r#"{"B":[]}"#);
+
+        let mut buf = [0; N];
+        let config = Config::default().pretty(true);
+        let len = crate::to_slice_with_config(&[A::A(1, 'x')], &mut buf, &config).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            "[\n  {\n    \"A\": [\n      1,\n      \"x\"\n    ]\n  }\n]"
+        );
+    }
+
     #[test]
     fn test_struct_variant() {
         #[derive(Serialize)]
@@ -1071,4 +2648,560 @@ mod tests {
         let sd3 = SimpleDecimal(22_222.777);
         assert_eq!(&*crate::to_string::<_, N>(&sd3).unwrap(), r#"22222.78"#);
     }
+
+    #[test]
+    fn slice_exact_fit() {
+        let buf = &mut [0u8; 7];
+        assert_eq!(crate::to_slice("hello", buf), Ok(7));
+        assert_eq!(buf, br#""hello""#);
+
+        assert_eq!(
+            crate::to_slice(&[1.5f32, 2.5], &mut [0u8; 9]),
+            Ok(r#"[1.5,2.5]"#.len())
+        );
+
+        assert_eq!(
+            crate::to_slice("hello", &mut [0u8; 6]),
+            Err(crate::ser::Error::BufferFull)
+        );
+    }
+
+    #[test]
+    fn high_water_mark() {
+        #[derive(Serialize)]
+        struct Reading<'a> {
+            sensor: &'a str,
+            value: i32,
+        }
+
+        let mut mark = crate::ser::HighWaterMark::new();
+        assert_eq!(mark.high_water_mark(), 0);
+
+        let largest = Reading {
+            sensor: "humidity",
+            value: -1000,
+        };
+        let len = crate::to_string::<_, N>(&largest).unwrap().len();
+
+        mark.serialize(&Reading {
+            sensor: "t",
+            value: 1,
+        })
+        .unwrap();
+        assert_eq!(mark.serialize(&largest), Ok(len));
+        mark.serialize(&[0u8; 3]).unwrap();
+        mark.serialize(&()).unwrap();
+        assert_eq!(mark.high_water_mark(), len);
+    }
+
+    #[test]
+    fn backends() {
+        use crate::ser::{Error, FmtWriter, Serializer, SerializerBackend, Slice};
+        use serde::Serialize;
+
+        const EXPECTED: &str = r#"[1,"two",[3.0]]"#;
+
+        fn serialize<B: SerializerBackend>(backend: B) -> B {
+            let mut ser = Serializer::with_backend(backend);
+            (1, "two", [3.0]).serialize(&mut ser).unwrap();
+            assert_eq!(ser.end(), EXPECTED.len());
+            ser.into_backend()
+        }
+
+        let buf = &mut [0u8; N];
+        serialize(Slice::new(buf));
+        assert_eq!(&buf[..EXPECTED.len()], EXPECTED.as_bytes());
+
+        assert_eq!(
+            &serialize(heapless::Vec::<u8, N>::new()),
+            EXPECTED.as_bytes()
+        );
+        assert_eq!(
+            serialize(FmtWriter::new(heapless::String::<N>::new())).into_inner(),
+            EXPECTED
+        );
+        #[cfg(feature = "alloc")]
+        assert_eq!(serialize(::alloc::vec::Vec::new()), EXPECTED.as_bytes());
+        #[cfg(feature = "arrayvec")]
+        assert_eq!(
+            &serialize(arrayvec::ArrayVec::<u8, N>::new()),
+            EXPECTED.as_bytes()
+        );
+
+        // Writes that don't fit leave the backend untouched
+        let mut slice = Slice::new(&mut buf[..4]);
+        assert_eq!(slice.extend_from_slice(b"12345"), Err(Error::BufferFull));
+        assert_eq!(slice.end(), 0);
+
+        // Writes that exactly fill the remaining space succeed
+        slice.push(b'1').unwrap();
+        assert_eq!(slice.extend_from_slice(b"234"), Ok(()));
+        assert_eq!(slice.end(), 4);
+        assert_eq!(slice.push(b'5'), Err(Error::BufferFull));
+        assert_eq!(&buf[..4], b"1234");
+
+        let mut vec = heapless::Vec::<u8, 4>::new();
+        assert_eq!(
+            SerializerBackend::extend_from_slice(&mut vec, b"12345"),
+            Err(Error::BufferFull)
+        );
+        assert_eq!(vec.end(), 0);
+
+        let mut writer = FmtWriter::new(heapless::String::<4>::new());
+        assert_eq!(writer.extend_from_slice(b"12345"), Err(Error::WriteFailed));
+        assert_eq!(writer.end(), 0);
+    }
+
+    #[test]
+    fn fmt_writer() {
+        use crate::ser::{Error, FmtWriter, SerializerBackend};
+
+        // Characters split across writes are passed on once complete
+        let text = "aä💣b";
+        let mut writer = FmtWriter::new(heapless::String::<N>::new());
+        for &c in text.as_bytes() {
+            writer.push(c).unwrap();
+        }
+        assert_eq!(writer.end(), text.len());
+        assert_eq!(writer.into_inner(), text);
+
+        let mut writer = FmtWriter::new(heapless::String::<N>::new());
+        let (start, end) = text.as_bytes().split_at(4);
+        writer.extend_from_slice(start).unwrap();
+        writer.extend_from_slice(end).unwrap();
+        assert_eq!(writer.into_inner(), text);
+
+        // Invalid UTF-8 is rejected before anything is written
+        let mut writer = FmtWriter::new(heapless::String::<N>::new());
+        assert_eq!(writer.extend_from_slice(b"a\xFF"), Err(Error::InvalidUtf8));
+        writer.push(0xC3).unwrap();
+        assert_eq!(writer.push(b'a'), Err(Error::InvalidUtf8));
+        assert_eq!(writer.extend_from_slice(b"\xA4a"), Ok(()));
+        assert_eq!(writer.end(), 3);
+        assert_eq!(writer.into_inner(), "äa");
+
+        let mut writer = FmtWriter::new(heapless::String::<N>::new());
+        writer.push(0xF0).unwrap();
+        assert_eq!(writer.push(0xC0), Err(Error::InvalidUtf8));
+    }
+
+    #[test]
+    fn to_slice_checked() {
+        struct RawBytes(&'static [u8]);
+
+        impl serde::Serialize for RawBytes {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        let buf = &mut [0u8; N];
+        assert_eq!(crate::ser::to_slice_checked(&("ä", 1), buf), Ok(8));
+        assert_eq!(crate::ser::to_slice_checked(&RawBytes(b"1.50"), buf), Ok(4));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "serialized JSON is not valid UTF-8 at byte 1 of 3")]
+    fn to_slice_checked_invalid_utf8() {
+        struct RawBytes(&'static [u8]);
+
+        impl serde::Serialize for RawBytes {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        let buf = &mut [0u8; N];
+        let _ = crate::ser::to_slice_checked(&[RawBytes(b"\xff")], buf);
+    }
+
+    #[test]
+    fn to_slice_from() {
+        #[derive(Serialize)]
+        struct Led<'a> {
+            led: bool,
+            color: &'a str,
+        }
+
+        let led = Led {
+            led: true,
+            color: "red",
+        };
+
+        let full = &mut [0u8; N];
+        let len = crate::to_slice(&led, full).unwrap();
+
+        let buf = &mut [0u8; N];
+        let n = crate::ser::to_slice_from(&led, buf, 4).unwrap();
+        assert_eq!(&buf[..n], &full[4..len]);
+        assert_eq!(&buf[..n], br#"d":true,"color":"red"}"#);
+
+        assert_eq!(crate::ser::to_slice_from(&led, buf, 0), Ok(len));
+        assert_eq!(crate::ser::to_slice_from(&led, buf, len), Ok(0));
+        assert_eq!(crate::ser::to_slice_from(&led, buf, len + 10), Ok(0));
+
+        // The remainder still has to fit
+        assert_eq!(
+            crate::ser::to_slice_from(&led, &mut buf[..4], 4),
+            Err(crate::ser::Error::BufferFull)
+        );
+    }
+
+    #[test]
+    fn block_serializer() {
+        use crate::ser::{BlockSerializer, ResumeToken};
+
+        #[derive(Serialize)]
+        struct Message<'a> {
+            id: u64,
+            name: &'a str,
+            values: [i16; 4],
+        }
+
+        let message = Message {
+            id: u64::MAX,
+            name: "block \"wise\" transfer",
+            values: [-1, 200, -3000, 4],
+        };
+
+        let buf = &mut [0u8; N];
+        let len = crate::to_slice(&message, buf).unwrap();
+
+        let block_serializer = BlockSerializer::new(&message);
+        let mut json = heapless::Vec::<u8, N>::new();
+        let mut resume = ResumeToken::default();
+        let mut blocks = 0;
+        loop {
+            let mut block = [0u8; 16];
+            let (block_len, next) = block_serializer
+                .serialize_block(&mut block, resume)
+                .unwrap();
+            json.extend_from_slice(&block[..block_len]).unwrap();
+            blocks += 1;
+            match next {
+                Some(token) => {
+                    // All blocks but the last are filled completely
+                    assert_eq!(block_len, 16);
+                    resume = token;
+                }
+                None => break,
+            }
+        }
+
+        assert_eq!(&json[..], &buf[..len]);
+        assert_eq!(blocks, (len + 15) / 16);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn alloc() {
+        assert_eq!(crate::ser::alloc::to_string(&[0, 1, 2]).unwrap(), "[0,1,2]");
+        assert_eq!(crate::ser::alloc::to_vec(&true).unwrap(), b"true");
+
+        // The vector grows as needed
+        let long = [u32::MAX; 64];
+        let json = crate::ser::alloc::to_string(&long[..]).unwrap();
+        assert_eq!(json.len(), 64 * 11 + 1);
+        assert!(json.starts_with("[4294967295,4294967295,"));
+        assert!(json.ends_with(",4294967295]"));
+    }
+
+    #[test]
+    #[cfg(feature = "arrayvec")]
+    fn arrayvec() {
+        use crate::ser::SerializerBackend;
+
+        assert_eq!(
+            &crate::ser::arrayvec::to_string::<_, N>(&[0, 1, 2]).unwrap(),
+            "[0,1,2]"
+        );
+        assert_eq!(
+            &crate::ser::arrayvec::to_vec::<_, N>(&true).unwrap()[..],
+            b"true"
+        );
+        assert_eq!(
+            crate::ser::arrayvec::to_vec::<_, 4>(&false),
+            Err(crate::ser::Error::BufferFull)
+        );
+
+        // Writes that don't fit leave the vector untouched
+        let mut vec = arrayvec::ArrayVec::<u8, 4>::new();
+        vec.push(b'1');
+        assert_eq!(
+            SerializerBackend::extend_from_slice(&mut vec, b"2345"),
+            Err(crate::ser::Error::BufferFull)
+        );
+        assert_eq!(vec.end(), 1);
+        assert_eq!(vec.remaining(), 3);
+    }
+
+    #[test]
+    fn config() {
+        use crate::ser::{Config, NonFiniteFloat};
+
+        #[derive(Serialize)]
+        enum Command {
+            Blink { times: u8, delay: Option<u16> },
+        }
+
+        #[derive(Serialize)]
+        struct Led<'a> {
+            name: &'a str,
+            color: Option<[u8; 3]>,
+            brightness: Option<u8>,
+            commands: &'a [Command],
+            tags: &'a [u8],
+        }
+
+        let led = Led {
+            name: "status",
+            color: None,
+            brightness: Some(7),
+            commands: &[Command::Blink {
+                times: 3,
+                delay: None,
+            }],
+            tags: &[],
+        };
+
+        let config = Config::default().pretty(true).skip_none(true);
+        assert_eq!(
+            to_string_with(&led, &config),
+            r#"{
+  "name": "status",
+  "brightness": 7,
+  "commands": [
+    {
+      "Blink": {
+        "times": 3
+      }
+    }
+  ],
+  "tags": []
+}"#
+        );
+        assert_eq!(
+            to_string_with(&led, &Config::default().skip_none(true)),
+            r#"{"name":"status","brightness":7,"commands":[{"Blink":{"times":3}}],"tags":[]}"#
+        );
+        assert_eq!(
+            to_string_with(&led, &Config::default()),
+            &*crate::to_string::<_, N>(&led).unwrap()
+        );
+
+        // The value of a newtype variant isn't a struct field, so it's kept
+        #[derive(Serialize)]
+        enum Brightness {
+            Fixed(Option<u8>),
+        }
+
+        let config = Config::default().skip_none(true);
+        assert_eq!(
+            to_string_with(&Brightness::Fixed(None), &config),
+            r#"{"Fixed":null}"#
+        );
+        assert_eq!(
+            to_string_with(&Brightness::Fixed(None), &config.pretty(true)),
+            "{\n  \"Fixed\": null\n}"
+        );
+        assert_eq!(
+            to_string_with(&[1, 2], &Config::default().pretty(true).indent("\t")),
+            "[\n\t1,\n\t2\n]"
+        );
+
+        let config = Config::default()
+            .escape_non_ascii(true)
+            .escape_solidus(true);
+        assert_eq!(
+            to_string_with("a/b ä 💣", &config),
+            r#""a\/b \u00E4 \uD83D\uDCA3""#
+        );
+
+        let config = Config::default().non_finite(NonFiniteFloat::Quoted);
+        assert_eq!(
+            to_string_with(&[f32::NAN, f32::INFINITY], &config),
+            r#"["NaN","Infinity"]"#
+        );
+        assert_eq!(
+            to_string_with(&f64::NEG_INFINITY, &config),
+            r#""-Infinity""#
+        );
+        assert_eq!(to_string_with(&f64::NAN, &Config::default()), "null");
+    }
+
+    #[test]
+    fn empty_struct_variant() {
+        use crate::ser::Config;
+
+        #[derive(Serialize)]
+        enum Command {
+            Reset {},
+            Blink { times: Option<u8> },
+        }
+
+        let blink = Command::Blink { times: None };
+
+        let config = Config::default();
+        assert_eq!(
+            to_string_with(&Command::Reset {}, &config),
+            r#"{"Reset":{}}"#
+        );
+        assert_eq!(
+            to_string_with(&blink, &config),
+            r#"{"Blink":{"times":null}}"#
+        );
+        let config = config.skip_none(true);
+        assert_eq!(to_string_with(&blink, &config), r#"{"Blink":{}}"#);
+
+        let config = Config::default().collapse_empty_struct_variants(true);
+        assert_eq!(to_string_with(&Command::Reset {}, &config), r#""Reset""#);
+        assert_eq!(
+            to_string_with(&blink, &config),
+            r#"{"Blink":{"times":null}}"#
+        );
+        let config = config.skip_none(true);
+        assert_eq!(to_string_with(&blink, &config), r#""Blink""#);
+        assert_eq!(
+            to_string_with(&Command::Blink { times: Some(2) }, &config),
+            r#"{"Blink":{"times":2}}"#
+        );
+        assert_eq!(
+            to_string_with(&Command::Reset {}, &config.pretty(true)),
+            r#""Reset""#
+        );
+    }
+
+    #[test]
+    fn debug_json() {
+        use crate::ser::debug_json;
+
+        assert_eq!(&*debug_json::<_, 32>(&[1, 2]), "[\n  1,\n  2\n]");
+        assert_eq!(&*debug_json::<_, 12>(&[1, 2]), "[\n  1,\n  2\n]");
+
+        // Truncated output ends with an ellipsis in place of what didn't fit
+        assert_eq!(&*debug_json::<_, 10>(&[1, 2]), "[\n  1,\n...");
+        assert_eq!(&*debug_json::<_, 8>(&[1, 2]), "[\n  1...");
+        assert_eq!(&*debug_json::<_, 6>("aäbc"), "\"a...");
+        assert_eq!(&*debug_json::<_, 2>(&[1, 2]), "");
+
+        // As are values that fail to serialize
+        struct Failing;
+
+        impl serde::Serialize for Failing {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::{Error, SerializeSeq};
+
+                let mut seq = serializer.serialize_seq(None)?;
+                seq.serialize_element(&1)?;
+                Err(S::Error::custom("failed"))
+            }
+        }
+
+        assert_eq!(&*debug_json::<_, 32>(&Failing), "[\n  1...");
+    }
+
+    #[test]
+    fn to_string_or_truncate() {
+        fn truncate<T: serde::Serialize + ?Sized, const L: usize>(
+            value: &T,
+        ) -> (heapless::String<L>, bool) {
+            crate::ser::to_string_or_truncate::<_, L>(value).unwrap()
+        }
+
+        let (json, truncated) = truncate::<_, 16>(&[1, 2, 3]);
+        assert_eq!((&*json, truncated), ("[1,2,3]", false));
+        let (json, truncated) = truncate::<_, 7>(&[1, 2, 3]);
+        assert_eq!((&*json, truncated), ("[1,2,3]", false));
+        let (json, truncated) = truncate::<_, 5>(&[1, 2, 3]);
+        assert_eq!((&*json, truncated), ("[1,2,", true));
+
+        // A partially written character is dropped
+        let (json, truncated) = truncate::<_, 3>("aä");
+        assert_eq!((&*json, truncated), ("\"a", true));
+    }
+
+    #[test]
+    fn max_depth() {
+        use crate::ser::{Config, Error};
+
+        let nested = [[[[[0u8]]]]];
+        let buf = &mut [0u8; N];
+
+        let len = crate::ser::to_slice_with_config(&nested, buf, &Config::default()).unwrap();
+        assert_eq!(&buf[..len], b"[[[[[0]]]]]");
+
+        let config = Config::default().max_depth(5);
+        let len = crate::ser::to_slice_with_config(&nested, buf, &config).unwrap();
+        assert_eq!(&buf[..len], b"[[[[[0]]]]]");
+
+        let config = Config::default().max_depth(4);
+        assert_eq!(
+            crate::ser::to_slice_with_config(&nested, buf, &config),
+            Err(Error::RecursionLimitExceeded)
+        );
+        assert_eq!(
+            crate::ser::to_slice_with_config(&0u8, buf, &Config::default().max_depth(0)),
+            Ok(1)
+        );
+
+        // Nesting is unlimited by default
+        struct Deep(usize);
+
+        impl serde::Serialize for Deep {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+
+                let mut seq = serializer.serialize_seq(None)?;
+                if self.0 > 1 {
+                    seq.serialize_element(&Deep(self.0 - 1))?;
+                }
+                seq.end()
+            }
+        }
+
+        let buf = &mut [0u8; 1000];
+        assert_eq!(crate::to_slice(&Deep(500), buf), Ok(1000));
+        assert_eq!(
+            crate::ser::to_slice_with_config(&Deep(500), buf, &Config::default()),
+            Ok(1000)
+        );
+        assert_eq!(
+            crate::ser::to_slice_with_config(&Deep(500), buf, &Config::default().max_depth(499)),
+            Err(Error::RecursionLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn json_string_writer() {
+        use crate::ser::{Error, JsonStringWriter, Serializer};
+        use core::fmt::Write;
+
+        let buf = &mut [0u8; N];
+        let mut ser = Serializer::new(buf);
+        let mut writer = JsonStringWriter::new(&mut ser).unwrap();
+        let key = "tab\t";
+        write!(writer, "{}={}", key, 1.5).unwrap();
+        writer.write_str("; \"quoted\"").unwrap();
+        writer.finish().unwrap();
+        let len = ser.end();
+
+        let expected = crate::to_string::<_, N>("tab\t=1.5; \"quoted\"").unwrap();
+        assert_eq!(&buf[..len], expected.as_bytes());
+
+        let buf = &mut [0u8; 4];
+        let mut ser = Serializer::new(buf);
+        let mut writer = JsonStringWriter::new(&mut ser).unwrap();
+        assert!(write!(writer, "abcd").is_err());
+        assert_eq!(writer.finish(), Err(Error::BufferFull));
+    }
 }
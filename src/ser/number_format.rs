@@ -0,0 +1,188 @@
+//! Formatting of floating point numbers
+
+use core::fmt::{self, Write as _};
+
+use crate::ser::{Error, Result, SerializerBackend};
+
+/// Writes finite floats as JSON numbers
+///
+/// The `Serializer` handles NaN and infinities itself (see
+/// [`Config::non_finite`](crate::ser::Config::non_finite)), so these methods are only called with
+/// finite values. The output must be a valid JSON number.
+///
+/// ```
+/// # use serde_json_core::ser::{Fixed, Serializer};
+/// # use serde::Serialize;
+/// let mut buf = [0; 16];
+/// let mut ser = Serializer::new(&mut buf).with_number_format(&Fixed(2));
+/// [1.0, 0.126].serialize(&mut ser).unwrap();
+/// let len = ser.end();
+/// assert_eq!(&buf[..len], b"[1.00,0.13]");
+/// ```
+pub trait NumberFormat {
+    /// Write `v` to `out`
+    fn write_f32(&self, v: f32, out: &mut dyn SerializerBackend) -> Result<()>;
+
+    /// Write `v` to `out`
+    fn write_f64(&self, v: f64, out: &mut dyn SerializerBackend) -> Result<()>;
+}
+
+/// The shortest representation that round-trips, as produced by [`ryu`]; the default
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Ryu;
+
+impl NumberFormat for Ryu {
+    fn write_f32(&self, v: f32, out: &mut dyn SerializerBackend) -> Result<()> {
+        out.extend_from_slice(ryu::Buffer::new().format_finite(v).as_bytes())
+    }
+
+    fn write_f64(&self, v: f64, out: &mut dyn SerializerBackend) -> Result<()> {
+        out.extend_from_slice(ryu::Buffer::new().format_finite(v).as_bytes())
+    }
+}
+
+/// Exactly this many digits after the decimal point, rounding as `core::fmt` does
+///
+/// `Fixed(0)` writes integers without a decimal point. The number may be written in several
+/// pieces, so a full buffer can leave part of it behind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Fixed(pub u8);
+
+impl NumberFormat for Fixed {
+    fn write_f32(&self, v: f32, out: &mut dyn SerializerBackend) -> Result<()> {
+        write_fmt(out, format_args!("{:.*}", usize::from(self.0), v))
+    }
+
+    fn write_f64(&self, v: f64, out: &mut dyn SerializerBackend) -> Result<()> {
+        write_fmt(out, format_args!("{:.*}", usize::from(self.0), v))
+    }
+}
+
+/// Up to this many digits after the decimal point, rounding as `core::fmt` does and then leaving out
+/// trailing zeros
+///
+/// `2.50` is written as `2.5` and `2.00` as `2`, without a decimal point. Negative numbers that
+/// round to zero are written as `0`. Like [`Fixed`], a full buffer can leave part of the number
+/// behind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Trimmed(pub u8);
+
+impl NumberFormat for Trimmed {
+    fn write_f32(&self, v: f32, out: &mut dyn SerializerBackend) -> Result<()> {
+        write_trimmed(out, format_args!("{:.*}", usize::from(self.0), v))
+    }
+
+    fn write_f64(&self, v: f64, out: &mut dyn SerializerBackend) -> Result<()> {
+        write_trimmed(out, format_args!("{:.*}", usize::from(self.0), v))
+    }
+}
+
+/// Writes the number formatted by `args`, holding back the parts that turn out to be redundant: the
+/// sign of a zero, the decimal point and zeros that no other digit follows
+fn write_trimmed(out: &mut dyn SerializerBackend, args: fmt::Arguments<'_>) -> Result<()> {
+    #[derive(Default)]
+    struct Pending {
+        minus: bool,
+        /// The integer part is `0`, after a pending `minus`
+        zero: bool,
+        /// The decimal point has been read
+        fraction: bool,
+        /// The decimal point has been written
+        point: bool,
+        zeros: usize,
+    }
+
+    struct Trim<'a> {
+        out: &'a mut dyn SerializerBackend,
+        pending: Pending,
+    }
+
+    impl Trim<'_> {
+        fn write_byte(&mut self, c: u8) -> Result<()> {
+            let pending = &mut self.pending;
+            match c {
+                b'-' => pending.minus = true,
+                b'.' => pending.fraction = true,
+                b'0' if pending.fraction => pending.zeros += 1,
+                b'0' if pending.minus => pending.zero = true,
+                _ => {
+                    if pending.minus {
+                        self.out.push(b'-')?;
+                    }
+                    if pending.zero {
+                        self.out.push(b'0')?;
+                    }
+                    if pending.fraction && !pending.point {
+                        self.out.push(b'.')?;
+                    }
+                    for _ in 0..pending.zeros {
+                        self.out.push(b'0')?;
+                    }
+                    self.out.push(c)?;
+
+                    *pending = Pending {
+                        fraction: pending.fraction,
+                        point: pending.fraction,
+                        ..Pending::default()
+                    };
+                }
+            }
+            Ok(())
+        }
+    }
+
+    struct Adapter<'a> {
+        trim: Trim<'a>,
+        error: Option<Error>,
+    }
+
+    impl fmt::Write for Adapter<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for &c in s.as_bytes() {
+                self.trim.write_byte(c).map_err(|e| {
+                    self.error = Some(e);
+                    fmt::Error
+                })?;
+            }
+            Ok(())
+        }
+    }
+
+    let mut adapter = Adapter {
+        trim: Trim {
+            out,
+            pending: Pending::default(),
+        },
+        error: None,
+    };
+    adapter
+        .write_fmt(args)
+        .map_err(|_| adapter.error.unwrap_or(Error::BufferFull))?;
+
+    // Only zeros: `-0.00` is written as `0`
+    if adapter.trim.pending.zero {
+        adapter.trim.out.push(b'0')?;
+    }
+    Ok(())
+}
+
+fn write_fmt(out: &mut dyn SerializerBackend, args: fmt::Arguments<'_>) -> Result<()> {
+    struct Adapter<'a> {
+        out: &'a mut dyn SerializerBackend,
+        error: Option<Error>,
+    }
+
+    impl fmt::Write for Adapter<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.out.extend_from_slice(s.as_bytes()).map_err(|e| {
+                self.error = Some(e);
+                fmt::Error
+            })
+        }
+    }
+
+    let mut adapter = Adapter { out, error: None };
+    adapter
+        .write_fmt(args)
+        .map_err(|_| adapter.error.unwrap_or(Error::BufferFull))
+}
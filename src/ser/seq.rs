@@ -1,19 +1,41 @@
 use serde::ser;
 
-use crate::ser::{Error, Result, Serializer};
+use crate::ser::{Error, Result, Serializer, SerializerBackend, Slice};
 
-pub struct SerializeSeq<'a, 'b> {
-    de: &'a mut Serializer<'b>,
+pub struct SerializeSeq<'a, 'b, B = Slice<'b>> {
+    de: &'a mut Serializer<'b, B>,
     first: bool,
 }
 
-impl<'a, 'b: 'a> SerializeSeq<'a, 'b> {
-    pub(crate) fn new(de: &'a mut Serializer<'b>) -> Self {
+impl<'a, 'b: 'a, B: SerializerBackend> SerializeSeq<'a, 'b, B> {
+    pub(crate) fn new(de: &'a mut Serializer<'b, B>) -> Self {
         SerializeSeq { de, first: true }
     }
 }
 
-impl<'a, 'b: 'a> ser::SerializeSeq for SerializeSeq<'a, 'b> {
+/// Writes a JSON array one element at a time; see [`Serializer::begin_array`]
+pub struct ArrayBuilder<'a, 'b, B = Slice<'b>>(SerializeSeq<'a, 'b, B>);
+
+impl<'a, 'b: 'a, B: SerializerBackend> ArrayBuilder<'a, 'b, B> {
+    pub(crate) fn new(seq: SerializeSeq<'a, 'b, B>) -> Self {
+        ArrayBuilder(seq)
+    }
+
+    /// Append `value` to the array
+    pub fn push_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(&mut self.0, value)
+    }
+
+    /// Close the array
+    pub fn finish(self) -> Result<()> {
+        ser::SerializeSeq::end(self.0)
+    }
+}
+
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeSeq for SerializeSeq<'a, 'b, B> {
     type Ok = ();
     type Error = Error;
 
@@ -21,9 +43,7 @@ impl<'a, 'b: 'a> ser::SerializeSeq for SerializeSeq<'a, 'b> {
     where
         T: ser::Serialize + ?Sized,
     {
-        if !self.first {
-            self.de.push(b',')?;
-        }
+        self.de.begin_element(self.first)?;
         self.first = false;
 
         value.serialize(&mut *self.de)?;
@@ -31,12 +51,45 @@ impl<'a, 'b: 'a> ser::SerializeSeq for SerializeSeq<'a, 'b> {
     }
 
     fn end(self) -> Result<Self::Ok> {
-        self.de.push(b']')?;
-        Ok(())
+        self.de.end_compound(b']', self.first)
+    }
+}
+
+/// Serializes the fields of a tuple variant as an array inside `{"Variant":...}`
+pub struct SerializeTupleVariant<'a, 'b, B = Slice<'b>> {
+    de: &'a mut Serializer<'b, B>,
+    first: bool,
+}
+
+impl<'a, 'b: 'a, B: SerializerBackend> SerializeTupleVariant<'a, 'b, B> {
+    pub(crate) fn new(de: &'a mut Serializer<'b, B>) -> Self {
+        SerializeTupleVariant { de, first: true }
+    }
+}
+
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeTupleVariant
+    for SerializeTupleVariant<'a, 'b, B>
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        self.de.begin_element(self.first)?;
+        self.first = false;
+
+        value.serialize(&mut *self.de)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.de.end_compound(b']', self.first)?;
+        self.de.end_compound(b'}', false)
     }
 }
 
-impl<'a, 'b: 'a> ser::SerializeTuple for SerializeSeq<'a, 'b> {
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeTuple for SerializeSeq<'a, 'b, B> {
     type Ok = ();
     type Error = Error;
 
@@ -52,7 +105,7 @@ impl<'a, 'b: 'a> ser::SerializeTuple for SerializeSeq<'a, 'b> {
     }
 }
 
-impl<'a, 'b: 'a> ser::SerializeTupleStruct for SerializeSeq<'a, 'b> {
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeTupleStruct for SerializeSeq<'a, 'b, B> {
     type Ok = ();
     type Error = Error;
 
@@ -1,19 +1,27 @@
-use serde::ser;
+use core::fmt;
 
-use crate::ser::{Error, Result, Serializer};
+use serde::ser::{self, Impossible};
 
-pub struct SerializeStruct<'a, 'b> {
-    ser: &'a mut Serializer<'b>,
+use crate::de::split_member;
+use crate::ser::{Error, Result, Serializer, SerializerBackend, Slice};
+
+pub struct SerializeStruct<'a, 'b, B = Slice<'b>> {
+    ser: &'a mut Serializer<'b, B>,
     first: bool,
+    start: usize,
 }
 
-impl<'a, 'b: 'a> SerializeStruct<'a, 'b> {
-    pub(crate) fn new(ser: &'a mut Serializer<'b>) -> Self {
-        SerializeStruct { ser, first: true }
+impl<'a, 'b: 'a, B: SerializerBackend> SerializeStruct<'a, 'b, B> {
+    pub(crate) fn new(ser: &'a mut Serializer<'b, B>) -> Self {
+        SerializeStruct {
+            ser,
+            first: true,
+            start: 0,
+        }
     }
 }
 
-impl<'a, 'b: 'a> ser::SerializeStruct for SerializeStruct<'a, 'b> {
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeStruct for SerializeStruct<'a, 'b, B> {
     type Ok = ();
     type Error = Error;
 
@@ -21,39 +29,48 @@ impl<'a, 'b: 'a> ser::SerializeStruct for SerializeStruct<'a, 'b> {
     where
         T: ser::Serialize + ?Sized,
     {
-        // XXX if `value` is `None` we not produce any output for this field
-        if !self.first {
-            self.ser.push(b',')?;
+        if !self.ser.selects(key) {
+            return Ok(());
         }
-        self.first = false;
-
-        self.ser.push(b'"')?;
-        self.ser.extend_from_slice(key.as_bytes())?;
-        self.ser.extend_from_slice(b"\":")?;
-
-        value.serialize(&mut *self.ser)?;
-
-        Ok(())
+        serialize_field(self.ser, &mut self.first, &mut self.start, key, value)
     }
 
     fn end(self) -> Result<Self::Ok> {
-        self.ser.push(b'}')?;
-        Ok(())
+        self.ser.end_compound(b'}', self.first)
     }
 }
 
-pub struct SerializeStructVariant<'a, 'b> {
-    ser: &'a mut Serializer<'b>,
+/// Writes `{"Variant":{` only once the first field is serialized, so that an empty variant can
+/// still be collapsed into `"Variant"`
+pub struct SerializeStructVariant<'a, 'b, B = Slice<'b>> {
+    ser: &'a mut Serializer<'b, B>,
+    variant: &'static str,
     first: bool,
+    start: usize,
 }
 
-impl<'a, 'b: 'a> SerializeStructVariant<'a, 'b> {
-    pub(crate) fn new(ser: &'a mut Serializer<'b>) -> Self {
-        SerializeStructVariant { ser, first: true }
+impl<'a, 'b: 'a, B: SerializerBackend> SerializeStructVariant<'a, 'b, B> {
+    pub(crate) fn new(ser: &'a mut Serializer<'b, B>, variant: &'static str) -> Self {
+        SerializeStructVariant {
+            ser,
+            variant,
+            first: true,
+            start: 0,
+        }
+    }
+
+    fn open(&mut self) -> Result<()> {
+        self.ser.begin_compound(b'{')?;
+        self.ser.begin_element(true)?;
+        ser::Serializer::serialize_str(&mut *self.ser, self.variant)?;
+        self.ser.key_separator()?;
+        self.ser.begin_compound(b'{')
     }
 }
 
-impl<'a, 'b: 'a> ser::SerializeStructVariant for SerializeStructVariant<'a, 'b> {
+impl<'a, 'b: 'a, B: SerializerBackend> ser::SerializeStructVariant
+    for SerializeStructVariant<'a, 'b, B>
+{
     type Ok = ();
     type Error = Error;
 
@@ -61,23 +78,232 @@ impl<'a, 'b: 'a> ser::SerializeStructVariant for SerializeStructVariant<'a, 'b>
     where
         T: ser::Serialize + ?Sized,
     {
-        // XXX if `value` is `None` we not produce any output for this field
-        if !self.first {
-            self.ser.push(b',')?;
+        if self.first && !skips(self.ser, value) {
+            self.open()?;
         }
-        self.first = false;
+        serialize_field(self.ser, &mut self.first, &mut self.start, key, value)
+    }
 
-        self.ser.push(b'"')?;
-        self.ser.extend_from_slice(key.as_bytes())?;
-        self.ser.extend_from_slice(b"\":")?;
+    fn end(mut self) -> Result<Self::Ok> {
+        if self.first {
+            if self.ser.config.collapse_empty_struct_variants {
+                return ser::Serializer::serialize_str(&mut *self.ser, self.variant);
+            }
+            self.open()?;
+        }
+        self.ser.end_compound(b'}', self.first)?;
+        self.ser.end_compound(b'}', false)
+    }
+}
 
-        value.serialize(&mut *self.ser)?;
+/// `start` is where the first field of the object begins
+fn serialize_field<B, T>(
+    ser: &mut Serializer<'_, B>,
+    first: &mut bool,
+    start: &mut usize,
+    key: &'static str,
+    value: &T,
+) -> Result<()>
+where
+    B: SerializerBackend,
+    T: ser::Serialize + ?Sized,
+{
+    if skips(ser, value) {
+        return Ok(());
+    }
 
-        Ok(())
+    let before = ser.end();
+    ser.begin_element(*first)?;
+    let member = ser.end();
+    if *first {
+        *start = member;
     }
+    *first = false;
 
-    fn end(self) -> Result<Self::Ok> {
-        self.ser.extend_from_slice(b"}}")?;
-        Ok(())
+    ser::Serializer::serialize_str(&mut *ser, key)?;
+    ser.key_separator()?;
+
+    value.serialize(&mut *ser)?;
+
+    if ser.config.sort_struct_fields && member != *start {
+        sort_last_member(ser, *start, before, member)?;
+    }
+    Ok(())
+}
+
+/// Moves the member written last, at `member..`, in front of the first member in `start..before`
+/// with a greater key
+///
+/// The members in `start..before` are already sorted, so this is one step of an insertion sort.
+/// They're separated by whatever `begin_element` wrote in `before..member`.
+fn sort_last_member<B>(
+    ser: &mut Serializer<'_, B>,
+    start: usize,
+    before: usize,
+    member: usize,
+) -> Result<()>
+where
+    B: SerializerBackend,
+{
+    let written = ser.backend.written_mut().ok_or(Error::SortingUnsupported)?;
+    let separator = member - before;
+
+    let insert_at = {
+        let (key, _) = split_member(&written[member..]).ok_or(Error::SortingUnsupported)?;
+
+        let mut at = start;
+        loop {
+            if at >= before {
+                return Ok(());
+            }
+            let (other, len) =
+                split_member(&written[at..before]).ok_or(Error::SortingUnsupported)?;
+            if key < other {
+                break at;
+            }
+            at += len + separator;
+        }
+    };
+
+    // `a, b, new` -> `new a, b, ` -> `new, a, b`
+    let len = written.len() - member;
+    written[insert_at..].rotate_right(len);
+    written[insert_at + len..].rotate_right(separator);
+    Ok(())
+}
+
+/// Whether the field holding `value` is left out entirely
+fn skips<B, T>(ser: &Serializer<'_, B>, value: &T) -> bool
+where
+    T: ser::Serialize + ?Sized,
+{
+    let skipped = IsSkipped {
+        none: ser.config.skip_none,
+        unit: ser.config.skip_unit,
+    };
+    (skipped.none || skipped.unit) && value.serialize(skipped).is_ok()
+}
+
+/// Succeeds only for the values the enabled options leave out: `None` for `skip_none`, and `()`
+/// and unit structs like `PhantomData` for `skip_unit`
+struct IsSkipped {
+    none: bool,
+    unit: bool,
+}
+
+/// Returned by `IsSkipped` for values that are serialized
+#[derive(Debug)]
+struct NotSkipped;
+
+impl fmt::Display for NotSkipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("value is not skipped")
+    }
+}
+
+impl ser::StdError for NotSkipped {}
+
+impl ser::Error for NotSkipped {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        NotSkipped
+    }
+}
+
+macro_rules! not_skipped {
+    ($($serialize_fn:ident($($arg:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $serialize_fn(self, $(_: $arg),*) -> core::result::Result<$ok, NotSkipped> {
+                Err(NotSkipped)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for IsSkipped {
+    type Ok = ();
+    type Error = NotSkipped;
+    type SerializeSeq = Impossible<(), NotSkipped>;
+    type SerializeTuple = Impossible<(), NotSkipped>;
+    type SerializeTupleStruct = Impossible<(), NotSkipped>;
+    type SerializeTupleVariant = Impossible<(), NotSkipped>;
+    type SerializeMap = Impossible<(), NotSkipped>;
+    type SerializeStruct = Impossible<(), NotSkipped>;
+    type SerializeStructVariant = Impossible<(), NotSkipped>;
+
+    fn serialize_none(self) -> core::result::Result<(), NotSkipped> {
+        self.none.then_some(()).ok_or(NotSkipped)
+    }
+
+    fn serialize_unit(self) -> core::result::Result<(), NotSkipped> {
+        self.unit.then_some(()).ok_or(NotSkipped)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> core::result::Result<(), NotSkipped> {
+        self.unit.then_some(()).ok_or(NotSkipped)
+    }
+
+    not_skipped! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u8(u8) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> core::result::Result<(), NotSkipped>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(NotSkipped)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> core::result::Result<(), NotSkipped>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(NotSkipped)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> core::result::Result<(), NotSkipped>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(NotSkipped)
+    }
+
+    fn collect_str<T>(self, _value: &T) -> core::result::Result<(), NotSkipped>
+    where
+        T: fmt::Display + ?Sized,
+    {
+        Err(NotSkipped)
     }
 }
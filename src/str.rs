@@ -11,20 +11,53 @@ pub enum EscapedStringFragment<'a> {
     Escaped(char),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 /// Errors occuring while unescaping strings.
 pub enum StringUnescapeError {
-    /// Failed to unescape a character due to an invalid escape sequence.
-    InvalidEscapeSequence,
+    /// A backslash is followed by a character that doesn't start an escape sequence, e.g. `\x`.
+    UnknownEscape(char),
+
+    /// A backslash ends the string.
+    UnterminatedEscape,
+
+    /// A `\u` escape is followed by fewer than four characters, e.g. `\u12`.
+    IncompleteUnicode,
+
+    /// The four characters following `\u` aren't all hexadecimal digits, e.g. `\uZZZZ`.
+    InvalidUnicodeDigits,
+
+    /// A UTF-16 surrogate isn't part of a surrogate pair, e.g. `\uD83D` on its own.
+    LoneSurrogate,
+
+    /// The `core::fmt::Write` sink passed to [`EscapedStr::write_unescaped`] failed.
+    WriteFailed,
 }
 
 impl fmt::Display for StringUnescapeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            StringUnescapeError::InvalidEscapeSequence => write!(
-                f,
-                "Failed to unescape a character due to an invalid escape sequence."
-            ),
+            StringUnescapeError::UnknownEscape(c) => {
+                write!(f, "Unknown escape sequence \"\\{}\".", c.escape_debug())
+            }
+            StringUnescapeError::UnterminatedEscape => {
+                write!(f, "The string ends in the middle of an escape sequence.")
+            }
+            StringUnescapeError::IncompleteUnicode => {
+                write!(f, "A \"\\u\" escape sequence has fewer than four digits.")
+            }
+            StringUnescapeError::InvalidUnicodeDigits => {
+                write!(
+                    f,
+                    "A \"\\u\" escape sequence has invalid hexadecimal digits."
+                )
+            }
+            StringUnescapeError::LoneSurrogate => {
+                write!(f, "A \"\\u\" escape sequence is a lone UTF-16 surrogate.")
+            }
+            StringUnescapeError::WriteFailed => {
+                write!(f, "Failed to write the unescaped string.")
+            }
         }
     }
 }
@@ -32,6 +65,23 @@ impl fmt::Display for StringUnescapeError {
 #[cfg(feature = "std")]
 impl std::error::Error for StringUnescapeError {}
 
+/// Splits the four hexadecimal digits of a `\u` escape sequence off the start of `s`
+fn split_utf16_code_unit(s: &str) -> Result<(u16, &str), StringUnescapeError> {
+    if s.len() < 4 {
+        return Err(StringUnescapeError::IncompleteUnicode);
+    }
+
+    if !s.as_bytes()[..4].iter().all(u8::is_ascii_hexdigit) {
+        return Err(StringUnescapeError::InvalidUnicodeDigits);
+    }
+
+    // The digits are ASCII, so they end on a char boundary
+    let (digits, rest) = s.split_at(4);
+    u16::from_str_radix(digits, 16)
+        .map(|code_unit| (code_unit, rest))
+        .or(Err(StringUnescapeError::InvalidUnicodeDigits))
+}
+
 fn unescape_next_fragment(
     escaped_string: &str,
 ) -> Result<(EscapedStringFragment<'_>, &str), StringUnescapeError> {
@@ -48,22 +98,34 @@ fn unescape_next_fragment(
             Some('r') => '\r',
             Some('t') => '\t',
             Some('u') => {
-                fn split_first_slice(s: &str, len: usize) -> Option<(&str, &str)> {
-                    Some((s.get(..len)?, s.get(len..)?))
-                }
+                let (code_unit, rest) = split_utf16_code_unit(escaped_string_chars.as_str())?;
 
-                let (escape_sequence, remaining_escaped_string_chars) =
-                    split_first_slice(escaped_string_chars.as_str(), 4)
-                        .ok_or(StringUnescapeError::InvalidEscapeSequence)?;
+                let code_point = match code_unit {
+                    0xD800..=0xDBFF => {
+                        // A high surrogate must be followed by an escaped low surrogate
+                        let rest = rest
+                            .strip_prefix("\\u")
+                            .ok_or(StringUnescapeError::LoneSurrogate)?;
+                        let (low, rest) = split_utf16_code_unit(rest)?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(StringUnescapeError::LoneSurrogate);
+                        }
 
-                escaped_string_chars = remaining_escaped_string_chars.chars();
+                        escaped_string_chars = rest.chars();
+                        0x10000
+                            + ((u32::from(code_unit) - 0xD800) << 10 | (u32::from(low) - 0xDC00))
+                    }
+                    0xDC00..=0xDFFF => return Err(StringUnescapeError::LoneSurrogate),
+                    _ => {
+                        escaped_string_chars = rest.chars();
+                        u32::from(code_unit)
+                    }
+                };
 
-                u32::from_str_radix(escape_sequence, 16)
-                    .ok()
-                    .and_then(char::from_u32)
-                    .ok_or(StringUnescapeError::InvalidEscapeSequence)?
+                char::from_u32(code_point).ok_or(StringUnescapeError::LoneSurrogate)?
             }
-            _ => return Err(StringUnescapeError::InvalidEscapeSequence),
+            Some(c) => return Err(StringUnescapeError::UnknownEscape(c)),
+            None => return Err(StringUnescapeError::UnterminatedEscape),
         };
 
         (
@@ -106,6 +168,166 @@ impl<'a> EscapedStr<'a> {
     pub fn fragments(&self) -> EscapedStringFragmentIter<'a> {
         EscapedStringFragmentIter(self.0)
     }
+
+    /// Returns the part of the string before the first escape sequence, which is the same escaped
+    /// or not.
+    ///
+    /// Only the rest after it has to be unescaped, so a string escaping just a few trailing
+    /// characters is mostly borrowed instead of copied.
+    ///
+    /// ```
+    /// # use serde_json_core::str::EscapedStr;
+    /// let escaped = EscapedStr(r"Party time \uD83C\uDF89");
+    /// let prefix = escaped.borrow_prefix();
+    /// assert_eq!(prefix, "Party time ");
+    ///
+    /// let mut rest = heapless::String::<4>::new();
+    /// EscapedStr(&escaped.0[prefix.len()..]).write_unescaped(&mut rest).unwrap();
+    /// assert_eq!(rest, "🎉");
+    /// ```
+    pub fn borrow_prefix(&self) -> &'a str {
+        let end = self.0.find('\\').unwrap_or(self.0.len());
+        &self.0[..end]
+    }
+
+    /// Writes the unescaped string to `out`, e.g. a `heapless::String`.
+    ///
+    /// Fails with [`StringUnescapeError::WriteFailed`] if `out` does, in which case the part of
+    /// the string before the failing fragment has already been written.
+    ///
+    /// ```
+    /// # use serde_json_core::str::EscapedStr;
+    /// let mut unescaped = heapless::String::<16>::new();
+    /// EscapedStr(r"Party\u0021 \uD83C\uDF89").write_unescaped(&mut unescaped).unwrap();
+    /// assert_eq!(unescaped, "Party! 🎉");
+    /// ```
+    pub fn write_unescaped(&self, out: &mut impl fmt::Write) -> Result<(), StringUnescapeError> {
+        for fragment in self.fragments() {
+            match fragment? {
+                EscapedStringFragment::NotEscaped(s) => out.write_str(s),
+                EscapedStringFragment::Escaped(c) => out.write_char(c),
+            }
+            .or(Err(StringUnescapeError::WriteFailed))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The raw bytes of a string value, borrowed from the input without unescaping them.
+///
+/// Unlike [`EscapedStr`] this doesn't promise any particular encoding to the user, which suits
+/// strings that are decoded further, e.g. base64 or hex payloads.
+///
+/// ```
+///     #[derive(serde::Deserialize)]
+///     struct Packet<'a> {
+///         #[serde(borrow)]
+///         payload: serde_json_core::str::BytesRef<'a>,
+///     }
+///
+///     let (packet, _) =
+///         serde_json_core::from_str::<Packet<'_>>(r#"{ "payload": "AQID\/w==" }"#).unwrap();
+///     assert_eq!(packet.payload.0, br"AQID\/w==");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BytesRef<'a>(pub &'a [u8]);
+
+impl<'a, 'de: 'a> serde::Deserialize<'de> for BytesRef<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        EscapedStr::deserialize(deserializer).map(|s| BytesRef(s.0.as_bytes()))
+    }
+}
+
+/// A JSON value kept as text, borrowed from the input without parsing it into a Rust type.
+///
+/// It's serialized verbatim, without escaping it again, so a JSON fragment built elsewhere can be
+/// spliced into a larger document. [`RawValue::new`] checks that the text is a single valid JSON
+/// value, so the output stays valid. For the same reason a deserialized `RawValue` has to be strict
+/// JSON, without comments or trailing commas, even if the [`Config`](crate::de::Config) accepts
+/// them elsewhere.
+///
+/// The text is only available when deserializing straight from JSON, so a `RawValue` can't be
+/// used in a `#[serde(flatten)]` field or an untagged enum, whose content serde buffers first. To
+/// keep the members that a struct doesn't know, see
+/// [`from_slice_with_extras`](crate::de::from_slice_with_extras) instead.
+///
+/// ```
+/// # use serde_json_core::str::RawValue;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Message<'a> {
+///     id: u32,
+///     #[serde(borrow)]
+///     payload: RawValue<'a>,
+/// }
+///
+/// let (message, _) =
+///     serde_json_core::from_str::<Message<'_>>(r#"{"id":1,"payload":{"a": [1, 2]}}"#).unwrap();
+/// assert_eq!(message.payload.get(), r#"{"a": [1, 2]}"#);
+///
+/// let message = Message {
+///     id: 2,
+///     payload: RawValue::new("[true,null]").unwrap(),
+/// };
+/// let json = serde_json_core::to_string::<_, 32>(&message).unwrap();
+/// assert_eq!(json, r#"{"id":2,"payload":[true,null]}"#);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawValue<'a>(pub(crate) &'a str);
+
+impl<'a> RawValue<'a> {
+    pub(crate) const NAME: &'static str = "__serde_json_core_raw_value__";
+
+    /// Wraps `json` after checking that it's a single JSON value, optionally surrounded by
+    /// whitespace.
+    #[cfg_attr(feature = "custom-error-messages-128", allow(clippy::result_large_err))]
+    pub fn new(json: &'a str) -> Result<Self, crate::de::Error> {
+        crate::de::validate(json.as_bytes())?;
+        Ok(RawValue(json))
+    }
+
+    /// Returns the JSON text of the value.
+    pub fn get(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl serde::Serialize for RawValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(Self::NAME, self.0)
+    }
+}
+
+impl<'a, 'de: 'a> serde::Deserialize<'de> for RawValue<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RawValueVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for RawValueVisitor {
+            type Value = RawValue<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("any JSON value")
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                <&str as serde::Deserialize<'de>>::deserialize(deserializer).map(RawValue)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(Self::NAME, RawValueVisitor)
+    }
 }
 
 /// An iterator over the `EscapedStringFragment`s of an escaped string.
@@ -0,0 +1,89 @@
+//! Serialize association arrays as JSON objects
+//!
+//! serde serializes `[(K, V); N]` as an array of pairs. This module serializes it as an object
+//! with one member per pair instead, which suits tiny maps that don't need `heapless`. The object
+//! must have exactly `N` members with distinct keys to be deserialized. Deserializing requires
+//! `K: Default` and `V: Default` to initialize the array before it is filled.
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Limits<'a> {
+//!     #[serde(borrow, with = "serde_json_core::with::assoc_map")]
+//!     channels: [(&'a str, u32); 2],
+//! }
+//!
+//! let json = r#"{"channels":{"left":3,"right":5}}"#;
+//! let (limits, _) = serde_json_core::from_str::<Limits<'_>>(json).unwrap();
+//! assert_eq!(limits.channels, [("left", 3), ("right", 5)]);
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserialize, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap};
+use serde::{Deserializer, Serializer};
+
+/// Serializes `pairs` as a JSON object
+pub fn serialize<S, K, V, const N: usize>(
+    pairs: &[(K, V); N],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize,
+    V: Serialize,
+{
+    let mut map = serializer.serialize_map(Some(N))?;
+    for (key, value) in pairs {
+        map.serialize_entry(key, value)?;
+    }
+    map.end()
+}
+
+/// Deserializes a JSON object of exactly `N` members with distinct keys
+pub fn deserialize<'de, D, K, V, const N: usize>(deserializer: D) -> Result<[(K, V); N], D::Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de> + Default + PartialEq,
+    V: Deserialize<'de> + Default,
+{
+    deserializer.deserialize_map(AssocMapVisitor(PhantomData))
+}
+
+struct AssocMapVisitor<K, V, const N: usize>(PhantomData<(K, V)>);
+
+impl<'de, K, V, const N: usize> Visitor<'de> for AssocMapVisitor<K, V, N>
+where
+    K: Deserialize<'de> + Default + PartialEq,
+    V: Deserialize<'de> + Default,
+{
+    type Value = [(K, V); N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "an object with {} members", N)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut pairs: [(K, V); N] = core::array::from_fn(|_| Default::default());
+        for i in 0..N {
+            let (key, value) = map
+                .next_entry()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            if pairs[..i].iter().any(|(other, _)| *other == key) {
+                return Err(de::Error::custom("duplicate key in object"));
+            }
+            pairs[i] = (key, value);
+        }
+
+        if map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+
+        Ok(pairs)
+    }
+}
@@ -0,0 +1,116 @@
+//! Serialize bytes as a base64 string
+//!
+//! Uses the standard alphabet with padding (RFC 4648, section 4). Serializes anything that is
+//! `AsRef<[u8]>` and deserializes into a `[u8; N]`, which the decoded data must fill exactly.
+
+use core::fmt::{self, Write as _};
+
+use serde::de::{self, Unexpected, Visitor};
+use serde::{Deserializer, Serializer};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+struct Base64<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Base64<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.0.chunks(3) {
+            let mut group = [0; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    let sextet = (n >> (18 - 6 * i)) & 0x3f;
+                    f.write_char(ALPHABET[sextet as usize] as char)?;
+                } else {
+                    f.write_char('=')?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serializes `bytes` as a base64 string
+pub fn serialize<S, T>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<[u8]> + ?Sized,
+{
+    serializer.collect_str(&Base64(bytes.as_ref()))
+}
+
+/// Deserializes a base64 string into `N` bytes
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(Base64Visitor)
+}
+
+struct Base64Visitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for Base64Visitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} base64-encoded bytes", N)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if v.len() != (N + 2) / 3 * 4 {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+
+        let invalid = || E::invalid_value(Unexpected::Str(v), &self);
+        let mut bytes = [0; N];
+        let mut len = 0;
+        let groups = v.len() / 4;
+
+        for (i, group) in v.as_bytes().chunks_exact(4).enumerate() {
+            let mut n = 0u32;
+            let mut padding = 0;
+            for &c in group {
+                let sextet = match c {
+                    // Padding may only end the last group
+                    b'=' if i + 1 == groups => {
+                        padding += 1;
+                        0
+                    }
+                    _ if padding > 0 => return Err(invalid()),
+                    _ => sextet(c).ok_or_else(invalid)?,
+                };
+                n = n << 6 | sextet;
+            }
+            if padding > 2 {
+                return Err(invalid());
+            }
+
+            for &byte in &n.to_be_bytes()[1..4 - padding] {
+                *bytes.get_mut(len).ok_or_else(invalid)? = byte;
+                len += 1;
+            }
+        }
+
+        if len == N {
+            Ok(bytes)
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+fn sextet(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
@@ -0,0 +1,57 @@
+//! Serialize two-variant fieldless enums as booleans
+//!
+//! The first variant is serialized as `true` and the second as `false`, and the other way around
+//! when deserializing. Serializing any other value, like a variant with fields, fails.
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! enum Power {
+//!     On,
+//!     Off,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Relay {
+//!     #[serde(with = "serde_json_core::with::bool_enum")]
+//!     power: Power,
+//! }
+//!
+//! let (relay, _) = serde_json_core::from_str::<Relay>(r#"{"power":false}"#).unwrap();
+//! assert!(matches!(relay.power, Power::Off));
+//! ```
+
+use serde::de::{value::U32Deserializer, Deserialize};
+use serde::ser::{self, Serialize};
+use serde::{Deserializer, Serializer};
+
+use super::variant_index::VariantIndex;
+
+/// Serializes the first variant of `value` as `true` and the second as `false`
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    match value.serialize(VariantIndex) {
+        Ok(0) => serializer.serialize_bool(true),
+        Ok(1) => serializer.serialize_bool(false),
+        _ => Err(ser::Error::custom(
+            "expected one of the first two unit variants",
+        )),
+    }
+}
+
+/// Deserializes `true` as the first variant and `false` as the second
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let index = if bool::deserialize(deserializer)? {
+        0
+    } else {
+        1
+    };
+    T::deserialize(U32Deserializer::new(index))
+}
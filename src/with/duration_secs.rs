@@ -0,0 +1,66 @@
+//! Serialize a [`Duration`] as a number of seconds
+//!
+//! serde itself serializes a `Duration` as `{"secs":..,"nanos":..}`; this module uses a single
+//! `f64` instead, e.g. `1.5` for one and a half seconds. Precision is limited to that of an `f64`,
+//! which is sub-nanosecond for durations below 2^22 seconds, about 48.5 days.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::time::Duration;
+
+use serde::de::{self, Unexpected, Visitor};
+use serde::{Deserializer, Serializer};
+
+/// Serializes `duration` as seconds
+pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Deserializes a `Duration` from a non-negative number of seconds
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_f64(SecondsVisitor)
+}
+
+struct SecondsVisitor;
+
+impl<'de> Visitor<'de> for SecondsVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a non-negative number of seconds")
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        // `Duration::from_secs_f64` panics on negative, non-finite and too large values
+        if v >= 0.0 && v < u64::MAX as f64 {
+            Ok(Duration::from_secs_f64(v))
+        } else {
+            Err(E::invalid_value(Unexpected::Float(v), &self))
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Duration::from_secs(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u64::try_from(v)
+            .map(Duration::from_secs)
+            .map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+}
@@ -0,0 +1,52 @@
+//! Serialize fieldless enums as their variant index
+//!
+//! Each variant is serialized as its position in the enum declaration, starting at `0`, which is
+//! more compact than its name. Integers without a matching variant fail to deserialize, as does
+//! serializing a variant with fields. Explicit discriminants like `A = 10` are not taken into
+//! account.
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! enum Mode {
+//!     Off,
+//!     Eco,
+//!     Boost,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Heater {
+//!     #[serde(with = "serde_json_core::with::enum_as_int")]
+//!     mode: Mode,
+//! }
+//!
+//! let (heater, _) = serde_json_core::from_str::<Heater>(r#"{"mode":2}"#).unwrap();
+//! assert!(matches!(heater.mode, Mode::Boost));
+//! ```
+
+use serde::de::{value::U32Deserializer, Deserialize};
+use serde::ser::{self, Serialize};
+use serde::{Deserializer, Serializer};
+
+use super::variant_index::VariantIndex;
+
+/// Serializes the unit variant `value` as its index
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    match value.serialize(VariantIndex) {
+        Ok(index) => serializer.serialize_u32(index),
+        Err(_) => Err(ser::Error::custom("expected a unit variant")),
+    }
+}
+
+/// Deserializes the variant at the given index
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(U32Deserializer::new(u32::deserialize(deserializer)?))
+}
@@ -0,0 +1,75 @@
+//! Serialize bytes as a string of hexadecimal digits
+//!
+//! Serializes anything that is `AsRef<[u8]>` as lowercase hex and deserializes hex of either case
+//! into a `[u8; N]`, which must be exactly `2 * N` digits long.
+
+use core::fmt;
+
+use serde::de::{self, Unexpected, Visitor};
+use serde::{Deserializer, Serializer};
+
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Hex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes `bytes` as a hex string
+pub fn serialize<S, T>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<[u8]> + ?Sized,
+{
+    serializer.collect_str(&Hex(bytes.as_ref()))
+}
+
+/// Deserializes a hex string into `N` bytes
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(HexVisitor)
+}
+
+struct HexVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for HexVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} hex digits", 2 * N)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if v.len() != 2 * N {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+
+        let mut bytes = [0; N];
+        for (byte, digits) in bytes.iter_mut().zip(v.as_bytes().chunks_exact(2)) {
+            match (hex_digit(digits[0]), hex_digit(digits[1])) {
+                (Some(high), Some(low)) => *byte = high << 4 | low,
+                _ => return Err(E::invalid_value(Unexpected::Str(v), &self)),
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
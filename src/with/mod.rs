@@ -0,0 +1,322 @@
+//! Modules for use with `#[serde(with = "...")]`
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Firmware {
+//!     #[serde(with = "serde_json_core::with::hex_bytes")]
+//!     sha256: [u8; 32],
+//! }
+//! ```
+
+pub mod array;
+pub mod assoc_map;
+pub mod base64_bytes;
+pub mod bool_enum;
+pub mod duration_secs;
+pub mod enum_as_int;
+pub mod hex_bytes;
+pub mod number_as_string;
+#[cfg(feature = "heapless")]
+pub mod unescaped;
+mod variant_index;
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::{Deserialize, Serialize};
+
+    const N: usize = 128;
+
+    #[test]
+    fn unescaped() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Event {
+            #[serde(with = "crate::with::unescaped")]
+            name: heapless::String<16>,
+        }
+
+        let (event, _) = crate::from_str::<Event>(r#"{"name":"Party\u0021"}"#).unwrap();
+        assert_eq!(event.name, "Party!");
+        assert_eq!(
+            &*crate::to_string::<_, N>(&event).unwrap(),
+            r#"{"name":"Party!"}"#
+        );
+
+        // Also works alongside a scratch buffer that's too small for the string
+        let (event, _) =
+            crate::from_str_escaped::<Event>(r#"{"name":"\"quoted\""}"#, &mut [0; 1]).unwrap();
+        assert_eq!(event.name, "\"quoted\"");
+
+        assert!(crate::from_str::<Event>(r#"{"name":"0123456789abcdef\n"}"#).is_err());
+        assert!(crate::from_str::<Event>(r#"{"name":"\x"}"#).is_err());
+    }
+
+    #[test]
+    fn assoc_map() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Limits<'a> {
+            #[serde(borrow, with = "crate::with::assoc_map")]
+            channels: [(&'a str, u32); 3],
+        }
+
+        let limits = Limits {
+            channels: [("left", 3), ("right", 5), ("center", 0)],
+        };
+        let json = crate::to_string::<_, N>(&limits).unwrap();
+        assert_eq!(&*json, r#"{"channels":{"left":3,"right":5,"center":0}}"#);
+        assert_eq!(
+            crate::from_str::<Limits<'_>>(&json),
+            Ok((limits, json.len()))
+        );
+
+        // Too few, too many and duplicate keys
+        assert!(crate::from_str::<Limits<'_>>(r#"{"channels":{"left":3,"right":5}}"#).is_err());
+        assert!(crate::from_str::<Limits<'_>>(
+            r#"{"channels":{"left":3,"right":5,"center":0,"rear":1}}"#
+        )
+        .is_err());
+        assert!(
+            crate::from_str::<Limits<'_>>(r#"{"channels":{"left":3,"right":5,"left":0}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn bool_enum() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        enum Power {
+            On,
+            Off,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Relay {
+            #[serde(with = "crate::with::bool_enum")]
+            power: Power,
+        }
+
+        for (power, json) in [
+            (Power::On, r#"{"power":true}"#),
+            (Power::Off, r#"{"power":false}"#),
+        ] {
+            let relay = Relay { power };
+            assert_eq!(&*crate::to_string::<_, N>(&relay).unwrap(), json);
+            assert_eq!(crate::from_str(json), Ok((relay, json.len())));
+        }
+
+        assert!(crate::from_str::<Relay>(r#"{"power":"On"}"#).is_err());
+
+        #[derive(Serialize)]
+        enum Level {
+            _Low,
+            _High,
+            Custom(u8),
+        }
+
+        #[derive(Serialize)]
+        struct Dimmer {
+            #[serde(with = "crate::with::bool_enum")]
+            level: Level,
+        }
+
+        assert_eq!(
+            crate::to_string::<_, N>(&Dimmer {
+                level: Level::Custom(5)
+            }),
+            Err(crate::ser::Error::CustomError)
+        );
+    }
+
+    #[test]
+    fn enum_as_int() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        enum Mode {
+            Off,
+            Eco,
+            Boost,
+            _Custom(u8),
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Heater {
+            #[serde(with = "crate::with::enum_as_int")]
+            mode: Mode,
+        }
+
+        for (mode, json) in [
+            (Mode::Off, r#"{"mode":0}"#),
+            (Mode::Eco, r#"{"mode":1}"#),
+            (Mode::Boost, r#"{"mode":2}"#),
+        ] {
+            let heater = Heater { mode };
+            assert_eq!(&*crate::to_string::<_, N>(&heater).unwrap(), json);
+            assert_eq!(crate::from_str(json), Ok((heater, json.len())));
+        }
+
+        // The variant with a field has an index, but can't be deserialized from it
+        assert!(crate::from_str::<Heater>(r#"{"mode":3}"#).is_err());
+        assert!(crate::from_str::<Heater>(r#"{"mode":4}"#).is_err());
+        assert!(crate::from_str::<Heater>(r#"{"mode":-1}"#).is_err());
+        assert!(crate::from_str::<Heater>(r#"{"mode":"Eco"}"#).is_err());
+        assert_eq!(
+            crate::to_string::<_, N>(&Heater {
+                mode: Mode::_Custom(1)
+            }),
+            Err(crate::ser::Error::CustomError)
+        );
+    }
+
+    #[test]
+    fn hex_bytes() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Key {
+            #[serde(with = "crate::with::hex_bytes")]
+            id: [u8; 4],
+        }
+
+        let key = Key {
+            id: [0x00, 0x1f, 0xa0, 0xff],
+        };
+        let json = r#"{"id":"001fa0ff"}"#;
+        assert_eq!(&*crate::to_string::<_, N>(&key).unwrap(), json);
+        assert_eq!(crate::from_str(json), Ok((key, json.len())));
+        assert_eq!(
+            crate::from_str(r#"{"id":"001FA0FF"}"#),
+            Ok((
+                Key {
+                    id: [0x00, 0x1f, 0xa0, 0xff]
+                },
+                17
+            ))
+        );
+
+        assert!(crate::from_str::<Key>(r#"{"id":"001fa0"}"#).is_err());
+        assert!(crate::from_str::<Key>(r#"{"id":"001fa0fff"}"#).is_err());
+        assert!(crate::from_str::<Key>(r#"{"id":"001fa0fg"}"#).is_err());
+        assert!(crate::from_str::<Key>(r#"{"id":[0,31,160,255]}"#).is_err());
+    }
+
+    #[test]
+    fn base64_bytes() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Blob<const L: usize> {
+            #[serde(with = "crate::with::base64_bytes")]
+            data: [u8; L],
+        }
+
+        fn round_trip<const L: usize>(data: [u8; L], json: &str) {
+            let blob = Blob { data };
+            assert_eq!(&*crate::to_string::<_, N>(&blob).unwrap(), json);
+            assert_eq!(crate::from_str(json), Ok((blob, json.len())));
+        }
+
+        round_trip([], r#"{"data":""}"#);
+        round_trip(*b"f", r#"{"data":"Zg=="}"#);
+        round_trip(*b"fo", r#"{"data":"Zm8="}"#);
+        round_trip(*b"foo", r#"{"data":"Zm9v"}"#);
+        round_trip(*b"foob", r#"{"data":"Zm9vYg=="}"#);
+        round_trip([0xfb, 0xff, 0xbf], r#"{"data":"+/+/"}"#);
+
+        // Wrong length, missing or misplaced padding, invalid characters
+        assert!(crate::from_str::<Blob<2>>(r#"{"data":"Zg=="}"#).is_err());
+        assert!(crate::from_str::<Blob<1>>(r#"{"data":"Zg"}"#).is_err());
+        assert!(crate::from_str::<Blob<4>>(r#"{"data":"Zg==Zm9v"}"#).is_err());
+        assert!(crate::from_str::<Blob<1>>(r#"{"data":"Z==="}"#).is_err());
+        assert!(crate::from_str::<Blob<3>>(r#"{"data":"Zm9*"}"#).is_err());
+    }
+
+    #[test]
+    fn number_as_string() {
+        use core::fmt::Write;
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Counters {
+            #[serde(with = "crate::with::number_as_string")]
+            total: u64,
+            #[serde(with = "crate::with::number_as_string")]
+            offset: i64,
+        }
+
+        let counters = Counters {
+            total: u64::MAX,
+            offset: i64::MIN,
+        };
+        let json = r#"{"total":"18446744073709551615","offset":"-9223372036854775808"}"#;
+        assert_eq!(&*crate::to_string::<_, N>(&counters).unwrap(), json);
+        assert_eq!(crate::from_str(json), Ok((counters, json.len())));
+
+        for total in [
+            r#""""#,
+            r#""18446744073709551616""#,
+            r#""-1""#,
+            r#"" 1""#,
+            r#""1 ""#,
+            r#""1.5""#,
+            r#""0x10""#,
+            r#""one""#,
+            "1",
+        ] {
+            let mut json = heapless::String::<N>::new();
+            write!(json, r#"{{"total":{},"offset":"0"}}"#, total).unwrap();
+            assert!(crate::from_str::<Counters>(&json).is_err(), "{}", total);
+        }
+    }
+
+    #[test]
+    fn array() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Frame {
+            #[serde(with = "crate::with::array")]
+            samples: [u8; 64],
+        }
+
+        let mut samples = [0; 64];
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample = i as u8;
+        }
+        let frame = Frame { samples };
+
+        let json = crate::to_string::<_, 256>(&frame).unwrap();
+        assert!(json.starts_with(r#"{"samples":[0,1,2,"#));
+        assert!(json.ends_with(",62,63]}"));
+        assert_eq!(crate::from_str(&json), Ok((frame, json.len())));
+
+        assert!(crate::from_str::<Frame>(r#"{"samples":[0,1,2]}"#).is_err());
+    }
+
+    #[test]
+    fn duration() {
+        use core::time::Duration;
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Timeouts {
+            // serde's own representation
+            idle: Duration,
+            #[serde(with = "crate::with::duration_secs")]
+            request: Duration,
+        }
+
+        let timeouts = Timeouts {
+            idle: Duration::new(90, 5),
+            request: Duration::from_millis(1500),
+        };
+        let json = r#"{"idle":{"secs":90,"nanos":5},"request":1.5}"#;
+        assert_eq!(&*crate::to_string::<_, N>(&timeouts).unwrap(), json);
+        assert_eq!(crate::from_str(json), Ok((timeouts, json.len())));
+
+        let (timeouts, _) =
+            crate::from_str::<Timeouts>(r#"{"idle":{"secs":0,"nanos":0},"request":0.000001}"#)
+                .unwrap();
+        assert_eq!(timeouts.request, Duration::from_micros(1));
+        let (timeouts, _) =
+            crate::from_str::<Timeouts>(r#"{"idle":{"secs":0,"nanos":0},"request":3}"#).unwrap();
+        assert_eq!(timeouts.request, Duration::from_secs(3));
+
+        assert!(
+            crate::from_str::<Timeouts>(r#"{"idle":{"secs":0,"nanos":0},"request":-1}"#).is_err()
+        );
+        assert!(
+            crate::from_str::<Timeouts>(r#"{"idle":{"secs":0,"nanos":0},"request":1e300}"#)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Serialize numbers as strings
+//!
+//! JavaScript represents all numbers as `f64`, so integers above 2^53 lose precision unless they're
+//! sent as strings. Serializes anything that is `Display` as a string and deserializes numbers
+//! from strings containing nothing but a JSON number, e.g. `"18446744073709551615"`.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, Unexpected, Visitor};
+use serde::{Deserializer, Serializer};
+
+/// Serializes `value` as a string
+pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: fmt::Display + ?Sized,
+{
+    serializer.collect_str(value)
+}
+
+/// Deserializes a number from a string
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    deserializer.deserialize_str(NumberVisitor(PhantomData))
+}
+
+struct NumberVisitor<T>(PhantomData<T>);
+
+impl<'de, T: DeserializeOwned> Visitor<'de> for NumberVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a string containing a number")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        // The JSON parser skips surrounding whitespace, which isn't part of the number
+        let number = match v.as_bytes() {
+            [b'-' | b'0'..=b'9', .., b'0'..=b'9'] | [b'0'..=b'9'] => {
+                crate::from_slice(v.as_bytes()).ok()
+            }
+            _ => None,
+        };
+
+        number
+            .map(|(number, _)| number)
+            .ok_or_else(|| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
@@ -0,0 +1,77 @@
+//! Unescape strings straight into a `heapless::String<N>`
+//!
+//! Deserializing a `heapless::String<N>` normally needs a scratch buffer passed to
+//! [`from_str_escaped`](crate::de::from_str_escaped) to unescape into. This module borrows the
+//! escaped string from the input instead and unescapes it into the `String` itself, so plain
+//! [`from_str`](crate::from_str) works. Serialization is unchanged.
+//!
+//! ```
+//! # use serde_derive::Deserialize;
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     #[serde(with = "serde_json_core::with::unescaped")]
+//!     name: heapless::String<16>,
+//! }
+//!
+//! let (event, _) = serde_json_core::from_str::<Event>(r#"{"name":"Party\u0021"}"#).unwrap();
+//! assert_eq!(event.name, "Party!");
+//! ```
+
+use core::fmt;
+
+use serde::de::{self, Deserialize as _, Unexpected};
+use serde::{Deserializer, Serializer};
+
+use crate::str::{EscapedStr, EscapedStringFragment, StringUnescapeError};
+
+/// Serializes `string` as a JSON string
+pub fn serialize<S, const N: usize>(
+    string: &heapless::String<N>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(string)
+}
+
+/// Deserializes a JSON string, unescaping it into a `heapless::String<N>`
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<heapless::String<N>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let escaped = EscapedStr::deserialize(deserializer)?;
+
+    let mut string = heapless::String::new();
+    match escaped.write_unescaped(&mut string) {
+        Ok(()) => Ok(string),
+        Err(StringUnescapeError::WriteFailed) => Err(de::Error::invalid_length(
+            unescaped_len(escaped),
+            &Capacity(N),
+        )),
+        Err(_) => Err(de::Error::invalid_value(
+            Unexpected::Str(escaped.0),
+            &"a valid escaped string",
+        )),
+    }
+}
+
+/// The length of `escaped` once unescaped, which is only needed to report an error
+fn unescaped_len(escaped: EscapedStr<'_>) -> usize {
+    escaped
+        .fragments()
+        .map(|fragment| match fragment {
+            Ok(EscapedStringFragment::NotEscaped(s)) => s.len(),
+            Ok(EscapedStringFragment::Escaped(c)) => c.len_utf8(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+struct Capacity(usize);
+
+impl de::Expected for Capacity {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a string of at most {} bytes", self.0)
+    }
+}
@@ -0,0 +1,124 @@
+//! Finding out which unit variant an enum value is
+
+use core::fmt;
+
+use serde::ser::{self, Impossible};
+
+/// Returns the index of unit variants and fails for anything else
+pub(crate) struct VariantIndex;
+
+/// Returned by `VariantIndex` for anything but a unit variant
+#[derive(Debug)]
+pub(crate) struct NotUnitVariant;
+
+impl fmt::Display for NotUnitVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("value is not a unit variant")
+    }
+}
+
+impl ser::StdError for NotUnitVariant {}
+
+impl ser::Error for NotUnitVariant {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        NotUnitVariant
+    }
+}
+
+macro_rules! not_unit_variant {
+    ($($serialize_fn:ident($($arg:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $serialize_fn(self, $(_: $arg),*) -> core::result::Result<$ok, NotUnitVariant> {
+                Err(NotUnitVariant)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for VariantIndex {
+    type Ok = u32;
+    type Error = NotUnitVariant;
+    type SerializeSeq = Impossible<u32, NotUnitVariant>;
+    type SerializeTuple = Impossible<u32, NotUnitVariant>;
+    type SerializeTupleStruct = Impossible<u32, NotUnitVariant>;
+    type SerializeTupleVariant = Impossible<u32, NotUnitVariant>;
+    type SerializeMap = Impossible<u32, NotUnitVariant>;
+    type SerializeStruct = Impossible<u32, NotUnitVariant>;
+    type SerializeStructVariant = Impossible<u32, NotUnitVariant>;
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> core::result::Result<u32, NotUnitVariant> {
+        Ok(variant_index)
+    }
+
+    not_unit_variant! {
+        serialize_bool(bool) -> u32;
+        serialize_i8(i8) -> u32;
+        serialize_i16(i16) -> u32;
+        serialize_i32(i32) -> u32;
+        serialize_i64(i64) -> u32;
+        serialize_u8(u8) -> u32;
+        serialize_u16(u16) -> u32;
+        serialize_u32(u32) -> u32;
+        serialize_u64(u64) -> u32;
+        serialize_f32(f32) -> u32;
+        serialize_f64(f64) -> u32;
+        serialize_char(char) -> u32;
+        serialize_str(&str) -> u32;
+        serialize_bytes(&[u8]) -> u32;
+        serialize_none() -> u32;
+        serialize_unit() -> u32;
+        serialize_unit_struct(&'static str) -> u32;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> core::result::Result<u32, NotUnitVariant>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(NotUnitVariant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> core::result::Result<u32, NotUnitVariant>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(NotUnitVariant)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> core::result::Result<u32, NotUnitVariant>
+    where
+        T: ser::Serialize + ?Sized,
+    {
+        Err(NotUnitVariant)
+    }
+
+    fn collect_str<T>(self, _value: &T) -> core::result::Result<u32, NotUnitVariant>
+    where
+        T: fmt::Display + ?Sized,
+    {
+        Err(NotUnitVariant)
+    }
+}